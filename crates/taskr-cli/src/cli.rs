@@ -0,0 +1,783 @@
+use std::{ops::RangeInclusive, path::PathBuf};
+
+use clap::{ArgGroup, Parser, Subcommand, ValueEnum};
+use taskr_core::{
+    condition, generate, parse, query,
+    recurrence::Recurrence,
+    store::{Priority, TaskStatus},
+};
+use time::{Duration, OffsetDateTime};
+
+use crate::utils::CONFIG_KEYS;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum SortKey {
+    Id,
+    /// Oldest first
+    Created,
+    /// Least recently updated first
+    Updated,
+    /// Soonest first, tasks without a due date last
+    Due,
+    /// Highest priority first, tasks without one last
+    Priority,
+    /// Todo, other statuses, someday and then complete
+    Status,
+    /// The order set with the move command
+    Manual,
+}
+
+impl From<SortKey> for query::SortKey {
+    fn from(key: SortKey) -> Self {
+        match key {
+            SortKey::Id => Self::Id,
+            SortKey::Created => Self::Created,
+            SortKey::Updated => Self::Updated,
+            SortKey::Due => Self::Due,
+            SortKey::Priority => Self::Priority,
+            SortKey::Status => Self::Status,
+            SortKey::Manual => Self::Manual,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    /// A checklist, completed tasks are checked
+    Markdown,
+    Json,
+    /// One line per task in the todo.txt format
+    Todotxt,
+    /// The JSON `task import` reads
+    Taskwarrior,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ImportFormat {
+    /// The todo.txt format, with priorities, +projects, @contexts and dates
+    Todotxt,
+    /// The JSON `task export` writes
+    Taskwarrior,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum TagAction {
+    Add,
+    Remove,
+}
+
+#[derive(Parser)]
+#[command(name = "Taskly", version = "0.1.0", about = "Manage tasks", long_about = None)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+
+    #[arg(long, global = true, value_enum, default_value_t = Output::Text)]
+    pub output: Output,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Keep tasks in this directory, overrides TASKLY_DATA_DIR and the config"
+    )]
+    pub data_dir: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        value_parser = parse::parse_profile,
+        help = "Use the tasks of this profile instead of the active one"
+    )]
+    pub profile: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        help = "When to color output, auto unless set in the config"
+    )]
+    pub color: Option<ColorMode>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ColorMode {
+    /// When printing to a terminal and NO_COLOR is not set
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Output {
+    /// For reading
+    Text,
+    /// JSON on stdout, for scripts, from the commands that print tasks or settings and from
+    /// add, quick and tmp add. Commands that only change tasks refuse it. Notes and warnings go
+    /// to stderr.
+    Json,
+    /// Tab separated fields, one task per line without headers
+    Plain,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum Commands {
+    #[command(about = "Create task")]
+    Add {
+        #[arg(
+            required_unless_present_any = ["file", "template"],
+            help = "Task description, or - to read it from stdin"
+        )]
+        description: Option<String>,
+
+        #[arg(
+            long,
+            conflicts_with = "description",
+            help = "Read the description from a file"
+        )]
+        file: Option<PathBuf>,
+
+        #[arg(
+            long,
+            conflicts_with_all = ["description", "file"],
+            help = "Fill in the description from a template in the config dir's templates/"
+        )]
+        template: Option<String>,
+
+        #[arg(
+            long = "var",
+            requires = "template",
+            value_parser = parse::parse_var,
+            help = "Value for a template placeholder, e.g. component=ui, can be repeated"
+        )]
+        vars: Vec<(String, String)>,
+
+        #[arg(
+            long,
+            value_parser = parse::parse_due,
+            help = "Due date, e.g. tomorrow, 2024-12-01, 2024-12-01T17:00 or 3d"
+        )]
+        due: Option<OffsetDateTime>,
+
+        #[arg(
+            long,
+            value_parser = parse::parse_duration,
+            help = "Remind in the shell summary this long before the due date, e.g. 2h or 1d"
+        )]
+        remind: Option<Duration>,
+
+        #[arg(short, long)]
+        priority: Option<Priority>,
+
+        #[arg(
+            short,
+            long = "tag",
+            value_parser = parse::parse_tag,
+            help = "Tag to add, can be repeated"
+        )]
+        tags: Vec<String>,
+
+        #[arg(long, value_parser = parse::parse_project)]
+        project: Option<String>,
+
+        #[arg(long, help = "Create the task as a subtask of this task")]
+        parent: Option<u64>,
+
+        #[arg(
+            long,
+            help = "Recreate the task when completed, e.g. daily, weekly, monthly or \"every 3 days\""
+        )]
+        repeat: Option<Recurrence>,
+
+        #[arg(long, help = "Do not apply the auto-tagging rules")]
+        no_rules: bool,
+    },
+    #[command(about = "Prompt for a single task and exit, for binding to a hotkey")]
+    Quick,
+    #[command(about = "Update task")]
+    Update {
+        #[arg()]
+        id: u64,
+
+        #[arg(required_unless_present_any = ["due", "no_due", "remind", "no_remind", "tags", "project", "no_project", "repeat", "no_repeat"])]
+        description: Option<String>,
+
+        #[arg(
+            long,
+            value_parser = parse::parse_due,
+            help = "Due date, e.g. tomorrow, 2024-12-01, 2024-12-01T17:00 or 3d"
+        )]
+        due: Option<OffsetDateTime>,
+
+        #[arg(long, conflicts_with = "due", help = "Remove the due date")]
+        no_due: bool,
+
+        #[arg(
+            long,
+            value_parser = parse::parse_duration,
+            help = "Remind in the shell summary this long before the due date, e.g. 2h or 1d"
+        )]
+        remind: Option<Duration>,
+
+        #[arg(long, conflicts_with = "remind", help = "Stop reminding of the task")]
+        no_remind: bool,
+
+        #[arg(
+            short,
+            long = "tag",
+            value_parser = parse::parse_tag,
+            help = "Tag to add, can be repeated"
+        )]
+        tags: Vec<String>,
+
+        #[arg(long, value_parser = parse::parse_project)]
+        project: Option<String>,
+
+        #[arg(
+            long,
+            conflicts_with = "project",
+            help = "Remove the task from its project"
+        )]
+        no_project: bool,
+
+        #[arg(
+            long,
+            help = "Recreate the task when completed, e.g. daily, weekly, monthly or \"every 3 days\""
+        )]
+        repeat: Option<Recurrence>,
+
+        #[arg(long, conflicts_with = "repeat", help = "Stop the task from recurring")]
+        no_repeat: bool,
+    },
+    #[command(about = "Delete task")]
+    Delete {
+        #[arg(required = true, value_parser = parse::parse_id_range, help = "IDs or ranges like 7-12")]
+        ids: Vec<RangeInclusive<u64>>,
+
+        #[arg(long, help = "Also delete the task's subtasks")]
+        cascade: bool,
+
+        #[arg(short, long, help = "Delete without asking for confirmation")]
+        yes: bool,
+    },
+    #[command(about = "List tasks")]
+    List {
+        #[arg(help = "Status to list, todo unless set in the config")]
+        status: Option<TaskStatus>,
+
+        #[arg(short, long)]
+        all: bool,
+
+        #[arg(
+            long,
+            value_parser = parse::parse_since,
+            help = "Only tasks created or modified since, e.g. yesterday, 2024-12-01 or 3d, of \
+                    any status unless one is given"
+        )]
+        changed_since: Option<OffsetDateTime>,
+
+        #[arg(long, value_enum, help = "Order of the tasks, by ID unless given")]
+        sort: Option<SortKey>,
+
+        #[arg(long, help = "Reverse the order")]
+        reverse: bool,
+
+        #[arg(short, long, help = "Show every field of each task instead of a table")]
+        long: bool,
+
+        #[arg(
+            short,
+            long = "tag",
+            value_parser = parse::parse_tag,
+            help = "Only tasks with this tag, can be repeated"
+        )]
+        tags: Vec<String>,
+
+        #[arg(long, value_parser = parse::parse_project)]
+        project: Option<String>,
+
+        #[arg(long, help = "List archived tasks instead, regardless of status")]
+        archived: bool,
+    },
+    #[command(about = "List open tasks due today or earlier")]
+    Today {
+        #[arg(short, long, help = "Show every field of each task instead of a table")]
+        long: bool,
+    },
+    #[command(about = "Summary and reminders for shell startup, at most once an hour")]
+    Motd {
+        #[arg(
+            long,
+            help = "Print the summary even if it was shown within the last hour"
+        )]
+        force: bool,
+    },
+    #[command(about = "Show a task with all of its fields and notes")]
+    Show {
+        #[arg()]
+        id: u64,
+    },
+    #[command(about = "Find tasks whose description or tags contain the query")]
+    Search {
+        #[arg(help = "Text to look for, case-insensitive")]
+        query: String,
+
+        #[arg(long, help = "Treat the query as a regular expression")]
+        regex: bool,
+
+        #[arg(long, help = "Only tasks with this status")]
+        status: Option<TaskStatus>,
+
+        #[arg(long, value_enum, help = "Order of the tasks, by ID unless given")]
+        sort: Option<SortKey>,
+
+        #[arg(long, help = "Reverse the order")]
+        reverse: bool,
+
+        #[arg(short, long, help = "Show every field of each task instead of a table")]
+        long: bool,
+
+        #[arg(
+            short,
+            long = "tag",
+            value_parser = parse::parse_tag,
+            help = "Only tasks with this tag, can be repeated"
+        )]
+        tags: Vec<String>,
+
+        #[arg(long, value_parser = parse::parse_project)]
+        project: Option<String>,
+
+        #[arg(long, help = "Search archived tasks instead")]
+        archived: bool,
+    },
+    #[command(about = "Set task status, e.g. todo or someday")]
+    Status {
+        #[arg(required = true, value_parser = parse::parse_id_range, help = "IDs or ranges like 7-12")]
+        ids: Vec<RangeInclusive<u64>>,
+
+        #[arg(help = "The new status, complete is deprecated in favour of the done command")]
+        status: TaskStatus,
+
+        #[arg(long, help = "Complete the task even if its dependencies are not")]
+        force: bool,
+    },
+    #[command(about = "Mark tasks as complete")]
+    Done {
+        #[arg(required = true, value_parser = parse::parse_id_range, help = "IDs or ranges like 7-12")]
+        ids: Vec<RangeInclusive<u64>>,
+
+        #[arg(long, help = "Complete tasks even if their dependencies are not")]
+        force: bool,
+    },
+    #[command(about = "Reopen completed tasks")]
+    Undone {
+        #[arg(required = true, value_parser = parse::parse_id_range, help = "IDs or ranges like 7-12")]
+        ids: Vec<RangeInclusive<u64>>,
+    },
+    #[command(about = "Set task priority")]
+    Priority {
+        #[arg()]
+        id: u64,
+
+        #[arg(help = "low, medium, high or critical")]
+        level: Priority,
+    },
+    #[command(about = "Manage projects")]
+    Project {
+        #[command(subcommand)]
+        command: ProjectCommand,
+    },
+    #[command(about = "Scratch tasks that belong to the current shell session")]
+    Tmp {
+        #[command(subcommand)]
+        command: TmpCommand,
+    },
+    #[command(about = "Switch between profiles, each with its own tasks")]
+    Context {
+        #[command(subcommand)]
+        command: ContextCommand,
+    },
+    #[command(about = "Read or change settings in config.toml")]
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    #[command(about = "Add or remove a task tag")]
+    Tag {
+        #[arg()]
+        id: u64,
+
+        #[arg(value_enum)]
+        action: TagAction,
+
+        #[arg(value_parser = parse::parse_tag)]
+        tag: String,
+    },
+    #[command(about = "Append a timestamped note to a task")]
+    Note {
+        #[arg()]
+        id: u64,
+
+        #[arg()]
+        text: String,
+    },
+    #[command(about = "Make task depend on another task")]
+    Depend {
+        #[arg()]
+        id: u64,
+
+        #[arg(long, help = "Task that has to be completed first")]
+        on: u64,
+    },
+    #[command(about = "Reorder task relative to another task")]
+    #[command(group(ArgGroup::new("position").required(true)))]
+    Move {
+        #[arg()]
+        id: u64,
+
+        #[arg(long, group = "position")]
+        before: Option<u64>,
+
+        #[arg(long, group = "position")]
+        after: Option<u64>,
+    },
+    #[command(about = "Move task to the someday/maybe list")]
+    Someday {
+        #[arg()]
+        id: u64,
+    },
+    #[command(about = "Block a task until external conditions hold, see maintain")]
+    #[command(group(ArgGroup::new("condition").required(true).multiple(true)))]
+    Block {
+        #[arg()]
+        id: u64,
+
+        #[arg(
+            long,
+            group = "condition",
+            value_parser = parse::parse_due,
+            help = "Once this has passed, e.g. 2025-07-01 or 3d"
+        )]
+        after: Option<OffsetDateTime>,
+
+        #[arg(long, group = "condition", help = "Once this file exists")]
+        file: Option<PathBuf>,
+
+        #[arg(
+            long,
+            group = "condition",
+            value_parser = condition::parse_url,
+            help = "Once a GET request to this URL returns 200"
+        )]
+        url: Option<String>,
+    },
+    #[command(about = "Unblock tasks whose conditions hold, e.g. from cron")]
+    Maintain,
+    #[command(about = "Move completed tasks out of the task list into the archive")]
+    Archive {
+        #[arg(
+            long,
+            value_parser = parse::parse_duration,
+            help = "Only tasks completed at least this long ago, e.g. 30d or 2w"
+        )]
+        older_than: Option<Duration>,
+    },
+    #[command(about = "Move an archived task back into the task list")]
+    Unarchive {
+        #[arg()]
+        id: u64,
+    },
+    #[command(about = "Review tasks that are due for a periodic look")]
+    Review {
+        #[arg(long, required = true)]
+        someday: bool,
+    },
+    #[command(about = "Summarise yesterday, today and blockers for a standup")]
+    Standup {
+        #[arg(
+            long,
+            default_value = "yesterday",
+            value_parser = parse::parse_since,
+            help = "Count completions since, e.g. yesterday or 24h"
+        )]
+        since: OffsetDateTime,
+
+        #[arg(long)]
+        markdown: bool,
+    },
+    #[command(about = "Handle a taskr:// URI, e.g. taskr://add?description=...")]
+    HandleUri {
+        #[arg(required_unless_present = "desktop_entry")]
+        uri: Option<String>,
+
+        #[arg(
+            long,
+            conflicts_with = "uri",
+            help = "Install a desktop entry registering taskly as the taskr:// handler"
+        )]
+        desktop_entry: bool,
+    },
+    #[command(about = "Populate the store with synthetic tasks")]
+    Generate {
+        #[arg(long, default_value_t = 100, value_parser = generate::parse_count)]
+        tasks: u64,
+
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    #[command(about = "Repair a tasks file that no longer loads, record by record")]
+    Rescue,
+    #[command(
+        about = "Show recently invoked commands, recorded when TASKLY_HISTORY=1, or applied rules",
+        group(ArgGroup::new("log").required(true))
+    )]
+    History {
+        #[arg(long, group = "log")]
+        commands: bool,
+
+        #[arg(
+            long,
+            group = "log",
+            help = "Show the auto-tagging rules applied to new tasks"
+        )]
+        rules: bool,
+
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+    #[command(about = "Repeat the last command that changed tasks")]
+    Again,
+    #[command(about = "Revert the last changes made to tasks")]
+    Undo {
+        #[arg(default_value_t = 1, help = "How many operations to revert")]
+        count: usize,
+    },
+    #[command(about = "Reapply changes reverted by undo")]
+    Redo {
+        #[arg(default_value_t = 1, help = "How many operations to reapply")]
+        count: usize,
+    },
+    #[command(about = "Export tasks as CSV, a Markdown checklist or JSON")]
+    Export {
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+
+        #[arg(long, help = "Only tasks with this status, every task unless given")]
+        status: Option<TaskStatus>,
+
+        #[arg(
+            short = 'o',
+            long = "to",
+            help = "Write to this file instead of stdout"
+        )]
+        file: Option<PathBuf>,
+    },
+    #[command(about = "Add tasks from a file in another format")]
+    Import {
+        #[arg(long, value_enum)]
+        format: ImportFormat,
+
+        #[arg()]
+        file: PathBuf,
+    },
+    #[command(about = "Show changes between a snapshot and the current tasks")]
+    Diff {
+        #[arg(required_unless_present = "since")]
+        snapshot: Option<PathBuf>,
+
+        #[arg(
+            long,
+            conflicts_with = "snapshot",
+            value_parser = parse::parse_since,
+            help = "Compare with the tasks as the undo journal has them at this time instead, \
+                    e.g. yesterday, 2024-12-01 or 3d"
+        )]
+        since: Option<OffsetDateTime>,
+    },
+}
+
+impl Commands {
+    /// Whether the command has JSON output, the others refuse `--output json`
+    pub fn supports_json(&self) -> bool {
+        // Rescue is interactive and export has its own format option
+        !matches!(self, Commands::Rescue | Commands::Export { .. })
+    }
+
+    /// Whether the command prints the IDs of the tasks it changed for `--output json`, rather
+    /// than JSON of its own
+    pub fn reports_changes(&self) -> bool {
+        match self {
+            Commands::Update { .. }
+            | Commands::Delete { .. }
+            | Commands::Status { .. }
+            | Commands::Done { .. }
+            | Commands::Undone { .. }
+            | Commands::Priority { .. }
+            | Commands::Tag { .. }
+            | Commands::Note { .. }
+            | Commands::Depend { .. }
+            | Commands::Move { .. }
+            | Commands::Someday { .. }
+            | Commands::Block { .. }
+            | Commands::Maintain
+            | Commands::Archive { .. }
+            | Commands::Unarchive { .. }
+            | Commands::Review { .. }
+            | Commands::Import { .. }
+            | Commands::HandleUri { .. }
+            | Commands::Generate { .. }
+            | Commands::Undo { .. }
+            | Commands::Redo { .. } => true,
+            Commands::Project { command } => !matches!(command, ProjectCommand::List),
+            Commands::Tmp { command } => matches!(command, TmpCommand::Clear { .. }),
+            Commands::Add { .. }
+            | Commands::Quick
+            | Commands::List { .. }
+            | Commands::Today { .. }
+            | Commands::Show { .. }
+            | Commands::Search { .. }
+            | Commands::Diff { .. }
+            | Commands::History { .. }
+            | Commands::Motd { .. }
+            | Commands::Standup { .. }
+            | Commands::Config { .. }
+            | Commands::Context { .. }
+            | Commands::Rescue
+            | Commands::Again
+            | Commands::Export { .. } => false,
+        }
+    }
+
+    /// Whether the command can change the store, these are what `again` repeats
+    pub fn is_mutating(&self) -> bool {
+        match self {
+            Commands::Add { .. }
+            | Commands::Quick
+            | Commands::Update { .. }
+            | Commands::Delete { .. }
+            | Commands::Status { .. }
+            | Commands::Done { .. }
+            | Commands::Undone { .. }
+            | Commands::Priority { .. }
+            | Commands::Tag { .. }
+            | Commands::Note { .. }
+            | Commands::Depend { .. }
+            | Commands::Move { .. }
+            | Commands::Someday { .. }
+            | Commands::Block { .. }
+            | Commands::Maintain
+            | Commands::Archive { .. }
+            | Commands::Unarchive { .. }
+            | Commands::Review { .. }
+            | Commands::Import { .. }
+            | Commands::HandleUri { .. }
+            | Commands::Generate { .. } => true,
+            // Repeating an undo would revert further back than the user looked
+            Commands::Undo { .. } | Commands::Redo { .. } => false,
+            Commands::Project { command } => !matches!(command, ProjectCommand::List),
+            Commands::Tmp { command } => !matches!(command, TmpCommand::List { .. }),
+            // Settings are not tasks, undo and again leave them alone
+            Commands::Config { .. } | Commands::Context { .. } => false,
+            Commands::List { .. }
+            | Commands::Today { .. }
+            | Commands::Motd { .. }
+            | Commands::Show { .. }
+            | Commands::Search { .. }
+            | Commands::Standup { .. }
+            | Commands::Export { .. }
+            | Commands::Diff { .. }
+            | Commands::Rescue
+            | Commands::History { .. }
+            | Commands::Again => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum ProjectCommand {
+    #[command(about = "List projects with their task counts")]
+    List,
+    #[command(about = "Create an empty project")]
+    Add {
+        #[arg(value_parser = parse::parse_project)]
+        name: String,
+    },
+    #[command(about = "Rename project, moving its tasks along")]
+    Rename {
+        #[arg(value_parser = parse::parse_project)]
+        old: String,
+
+        #[arg(value_parser = parse::parse_project)]
+        new: String,
+    },
+    #[command(about = "Delete project, its tasks are kept without a project")]
+    Delete {
+        #[arg(value_parser = parse::parse_project)]
+        name: String,
+    },
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum TmpCommand {
+    #[command(about = "Create a temporary task in the current session")]
+    Add {
+        #[arg()]
+        description: String,
+
+        #[arg(
+            long,
+            value_parser = parse::parse_duration,
+            default_value = "1d",
+            help = "Remove the task after this long even if the session never clears it"
+        )]
+        ttl: Duration,
+    },
+    #[command(about = "List the temporary tasks of the current session")]
+    List {
+        #[arg(long, help = "List those of every session")]
+        all: bool,
+    },
+    #[command(about = "Delete the temporary tasks of the current session")]
+    Clear {
+        #[arg(long, help = "Delete those of every session")]
+        all: bool,
+
+        #[arg(
+            long,
+            conflicts_with = "all",
+            help = "Only delete expired ones, of any session"
+        )]
+        expired: bool,
+    },
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum ConfigCommand {
+    #[command(about = "Print the value of a setting, nothing when it is not set")]
+    Get {
+        #[arg(value_parser = CONFIG_KEYS)]
+        key: String,
+    },
+    #[command(about = "Change a setting, creating the file if needed")]
+    Set {
+        #[arg(value_parser = CONFIG_KEYS)]
+        key: String,
+
+        #[arg()]
+        value: String,
+    },
+    #[command(about = "Print where the config file is")]
+    Path,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum ContextCommand {
+    #[command(about = "List profiles, marking the active one")]
+    List,
+    #[command(about = "Make a profile the active one, creating it if needed")]
+    Switch {
+        #[arg(value_parser = parse::parse_profile)]
+        name: String,
+    },
+}
@@ -0,0 +1,151 @@
+use std::{
+    env,
+    fmt::{self, Display},
+    io::{self, IsTerminal},
+    str::FromStr,
+};
+
+use taskr_core::store::{Priority, TaskStatus};
+
+use crate::cli::ColorMode;
+
+/// Set to anything to turn color off, see https://no-color.org
+const NO_COLOR_ENV: &str = "NO_COLOR";
+const RESET: &str = "\x1b[0m";
+const STYLE_EXAMPLES: &str = "red, bold green, bright_blue or none";
+
+/// Terminal text attributes, e.g. `bold red`, stored as their SGR parameters
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Style {
+    codes: Vec<u8>,
+    /// The text it was parsed from, kept for messages
+    name: String,
+}
+
+impl Style {
+    fn named(name: &str) -> Self {
+        name.parse().expect("Built-in styles are valid")
+    }
+
+    /// `text` wrapped in the escape sequences of the style
+    pub fn paint(&self, text: &str) -> String {
+        if self.codes.is_empty() || text.is_empty() {
+            return text.to_string();
+        }
+        let codes = self
+            .codes
+            .iter()
+            .map(u8::to_string)
+            .collect::<Vec<_>>()
+            .join(";");
+        format!("\x1b[{codes}m{text}{RESET}")
+    }
+}
+
+impl FromStr for Style {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const COLORS: [&str; 8] = [
+            "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+        ];
+
+        let mut codes = Vec::new();
+        for word in s.split_whitespace() {
+            let word = word.to_lowercase();
+            let code = match word.as_str() {
+                "none" => continue,
+                "bold" => 1,
+                "dim" => 2,
+                "italic" => 3,
+                "underline" => 4,
+                "reverse" => 7,
+                word => {
+                    let (bright, color) = match word.strip_prefix("bright_") {
+                        Some(color) => (true, color),
+                        None => (false, word),
+                    };
+                    let index = COLORS
+                        .iter()
+                        .position(|c| *c == color)
+                        .ok_or_else(|| format!("unknown style '{s}', try {STYLE_EXAMPLES}"))?;
+                    let base = if bright { 90 } else { 30 };
+                    base + index as u8
+                }
+            };
+            codes.push(code);
+        }
+        Ok(Self {
+            codes,
+            name: s.trim().to_string(),
+        })
+    }
+}
+
+impl Display for Style {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// Styles for parts of the task output, each can be changed in the config
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub todo: Style,
+    pub complete: Style,
+    pub someday: Style,
+    /// Custom statuses such as blocked
+    pub other: Style,
+    pub overdue: Style,
+    pub high: Style,
+    pub critical: Style,
+    /// Search matches
+    pub matched: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            todo: Style::default(),
+            complete: Style::named("green"),
+            someday: Style::named("dim"),
+            other: Style::named("yellow"),
+            overdue: Style::named("red"),
+            high: Style::named("yellow"),
+            critical: Style::named("bold red"),
+            matched: Style::named("reverse"),
+        }
+    }
+}
+
+impl Theme {
+    pub fn status(&self, status: &TaskStatus) -> &Style {
+        match status {
+            TaskStatus::Todo => &self.todo,
+            TaskStatus::Complete => &self.complete,
+            TaskStatus::Someday => &self.someday,
+            TaskStatus::InProgress | TaskStatus::Other(_) => &self.other,
+        }
+    }
+
+    /// Low and medium priorities are not highlighted
+    pub fn priority(&self, priority: Option<Priority>) -> Option<&Style> {
+        match priority? {
+            Priority::High => Some(&self.high),
+            Priority::Critical => Some(&self.critical),
+            Priority::Low | Priority::Medium => None,
+        }
+    }
+}
+
+/// Whether to color stdout. Auto colors a terminal unless `NO_COLOR` is set.
+pub fn enabled(mode: ColorMode) -> bool {
+    match mode {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => {
+            env::var_os(NO_COLOR_ENV).is_none_or(|value| value.is_empty())
+                && io::stdout().is_terminal()
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use std::ffi::OsString;
+
+use crate::utils::APPNAME;
+
+/// An invocation that still works but has a replacement. It is rewritten to the new form
+/// before parsing and kept for at least one release after the replacement ships.
+pub struct Deprecation {
+    pub old: &'static str,
+    pub new: &'static str,
+    /// Gets the command and its arguments, without the program name and global options
+    rewrite: fn(&[OsString]) -> Option<Vec<OsString>>,
+}
+
+pub const DEPRECATIONS: &[Deprecation] = &[Deprecation {
+    old: "status <id>... complete",
+    new: "done <id>...",
+    rewrite: status_complete,
+}];
+
+/// Global options that take a value, so the value is not mistaken for the command
+const GLOBAL_OPTIONS: &[&str] = &["--output", "--data-dir", "--profile", "--color"];
+
+/// Rewrites deprecated invocations in `args`, which include the program name, returning the
+/// arguments to parse and a notice for every rewrite that applied
+pub fn rewrite(args: Vec<OsString>) -> (Vec<OsString>, Vec<String>) {
+    let Some((program, rest)) = args.split_first() else {
+        return (args, Vec::new());
+    };
+    let (global, mut command) = split_global(rest);
+    let mut notices = Vec::new();
+    for deprecation in DEPRECATIONS {
+        if let Some(rewritten) = (deprecation.rewrite)(&command) {
+            command = rewritten;
+            notices.push(format!(
+                "'{APPNAME} {}' is deprecated and will be removed after the next release, use \
+                 '{APPNAME} {}' instead",
+                deprecation.old, deprecation.new
+            ));
+        }
+    }
+    if notices.is_empty() {
+        return (args, notices);
+    }
+
+    let mut rewritten = vec![program.clone()];
+    rewritten.extend(global);
+    rewritten.extend(command);
+    (rewritten, notices)
+}
+
+/// Splits the global options and their values out of the arguments after the program name.
+/// Everything after `--` belongs to the command.
+fn split_global(args: &[OsString]) -> (Vec<OsString>, Vec<OsString>) {
+    let mut global = Vec::new();
+    let mut command = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let Some(text) = arg.to_str() else {
+            command.push(arg.clone());
+            continue;
+        };
+        if text == "--" {
+            command.push(arg.clone());
+            command.extend(args.by_ref().cloned());
+            break;
+        }
+        let (name, value) = text
+            .split_once('=')
+            .map_or((text, None), |(n, v)| (n, Some(v)));
+        if !GLOBAL_OPTIONS.contains(&name) {
+            command.push(arg.clone());
+            continue;
+        }
+        global.push(arg.clone());
+        if value.is_none()
+            && let Some(value) = args.next()
+        {
+            global.push(value.clone());
+        }
+    }
+    (global, command)
+}
+
+/// `status 1 2 complete [--force]` -> `done 1 2 [--force]`
+fn status_complete(args: &[OsString]) -> Option<Vec<OsString>> {
+    if args.first()? != "status" {
+        return None;
+    }
+    let is_flag = |arg: &OsString| arg.to_str().is_some_and(|arg| arg.starts_with('-'));
+    let status = args.iter().rposition(|arg| !is_flag(arg))?;
+    let is_complete = args[status]
+        .to_str()
+        .is_some_and(|status| status.trim().eq_ignore_ascii_case("complete"));
+    // Needs at least one ID between the command and the status
+    let ids = args[1..status].iter().filter(|arg| !is_flag(arg)).count();
+    if !is_complete || ids == 0 {
+        return None;
+    }
+
+    let mut rewritten = args.to_vec();
+    rewritten.remove(status);
+    rewritten[0] = "done".into();
+    Some(rewritten)
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::cli::{Cli, Commands};
+
+    fn args(line: &str) -> Vec<OsString> {
+        line.split_whitespace().map(OsString::from).collect()
+    }
+
+    fn rewritten(line: &str) -> Option<String> {
+        let (rewritten, notices) = rewrite(args(line));
+        if notices.is_empty() {
+            assert_eq!(rewritten, args(line), "changed without a notice");
+            return None;
+        }
+        let rewritten = rewritten
+            .iter()
+            .map(|arg| arg.to_str().unwrap())
+            .collect::<Vec<_>>();
+        Some(rewritten.join(" "))
+    }
+
+    #[test]
+    fn status_complete_becomes_done() {
+        assert_eq!(
+            rewritten("taskr status 1 complete").unwrap(),
+            "taskr done 1"
+        );
+        assert_eq!(
+            rewritten("taskr status 1 4-6 Complete").unwrap(),
+            "taskr done 1 4-6"
+        );
+        assert_eq!(
+            rewritten("taskr status 1 complete --force").unwrap(),
+            "taskr done 1 --force"
+        );
+        assert_eq!(
+            rewritten("taskr status --force 1 complete").unwrap(),
+            "taskr done --force 1"
+        );
+    }
+
+    #[test]
+    fn global_options_before_the_command() {
+        assert_eq!(
+            rewritten("taskr --data-dir /tmp/tasks status 1 complete").unwrap(),
+            "taskr --data-dir /tmp/tasks done 1"
+        );
+        assert_eq!(
+            rewritten("taskr --output=json --color never status 2 complete").unwrap(),
+            "taskr --output=json --color never done 2"
+        );
+        assert_eq!(
+            rewritten("taskr --profile work status 3 complete --output plain").unwrap(),
+            "taskr --profile work --output plain done 3"
+        );
+    }
+
+    #[test]
+    fn global_option_values_are_not_commands() {
+        assert_eq!(rewritten("taskr --profile status list"), None);
+        assert_eq!(rewritten("taskr --data-dir status status 1 todo"), None);
+        assert_eq!(
+            rewritten("taskr --data-dir status status 1 complete").unwrap(),
+            "taskr --data-dir status done 1"
+        );
+    }
+
+    #[test]
+    fn current_forms_are_kept() {
+        assert_eq!(rewritten("taskr"), None);
+        assert_eq!(rewritten("taskr status 1 todo"), None);
+        assert_eq!(rewritten("taskr status 1 someday --force"), None);
+        assert_eq!(rewritten("taskr status complete"), None);
+        assert_eq!(rewritten("taskr done 1 complete"), None);
+        assert_eq!(rewritten("taskr add status 1 complete"), None);
+        assert_eq!(rewritten("taskr add -- status 1 complete"), None);
+    }
+
+    #[test]
+    fn rewrites_parse_as_the_replacement() {
+        let (args, _) = rewrite(args(
+            "taskr --data-dir /tmp/tasks status 1 3-4 complete --force",
+        ));
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.data_dir.unwrap().to_str(), Some("/tmp/tasks"));
+        let Some(Commands::Done { ids, force }) = cli.command else {
+            panic!("not rewritten to done");
+        };
+        assert_eq!(ids, [1..=1, 3..=4]);
+        assert!(force);
+    }
+}
@@ -0,0 +1,142 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    io,
+    path::PathBuf,
+};
+
+use taskr_core::{defaults::DefaultsError, rules::RuleError, store::StoreError};
+
+use crate::{
+    uri::UriError,
+    utils::{APPNAME, ConfigError, DirError},
+};
+
+// Process exit codes, so scripts can tell failures apart. 2 is what clap uses for bad arguments.
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_NOT_FOUND: i32 = 3;
+pub const EXIT_CONFLICT: i32 = 4;
+pub const EXIT_IO: i32 = 5;
+pub const EXIT_CORRUPT: i32 = 6;
+
+#[derive(Debug)]
+pub enum TaskError {
+    Store(StoreError),
+    Dir(DirError),
+    Uri(UriError),
+    Rules(RuleError),
+    Defaults(DefaultsError),
+    Config(ConfigError),
+    Io(PathBuf, io::Error),
+    /// Tasks a bulk command could not find, after it applied the rest
+    Missing(Vec<u64>),
+    /// Arguments that parse but make no sense, e.g. a task depending on itself
+    Invalid(String),
+    /// Refused because of the state of other tasks, e.g. completing a blocked task
+    Conflict(String),
+    /// The store could not be loaded, with the backup to restore it from if there is one
+    Load(StoreError, Option<PathBuf>),
+    /// Saving failed, the details have already been reported
+    Unsaved,
+    Other(String),
+}
+
+impl TaskError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Store(e) | Self::Load(e, _) => match e {
+                StoreError::NotFound(_) | StoreError::ProjectNotFound(_) => EXIT_NOT_FOUND,
+                StoreError::HasSubtasks(..)
+                | StoreError::DependencyCycle(..)
+                | StoreError::JournalConflict(..)
+                | StoreError::RepeatOutOfRange(_)
+                | StoreError::ProjectExists(_) => EXIT_CONFLICT,
+                StoreError::Io(..) => EXIT_IO,
+                StoreError::Parse(..) => EXIT_CORRUPT,
+            },
+            Self::Missing(_) => EXIT_NOT_FOUND,
+            Self::Rules(RuleError::Io(..)) => EXIT_IO,
+            Self::Rules(_) => EXIT_USAGE,
+            Self::Defaults(DefaultsError::Io(..)) => EXIT_IO,
+            Self::Defaults(_) => EXIT_USAGE,
+            Self::Config(ConfigError::Io(..)) => EXIT_IO,
+            Self::Config(_) => EXIT_USAGE,
+            Self::Uri(_) | Self::Invalid(_) => EXIT_USAGE,
+            Self::Conflict(_) => EXIT_CONFLICT,
+            Self::Dir(_) | Self::Io(..) | Self::Unsaved => EXIT_IO,
+            Self::Other(_) => EXIT_FAILURE,
+        }
+    }
+}
+
+impl Display for TaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Store(e) => write!(f, "{e}"),
+            Self::Dir(e) => write!(f, "{}", e.to_string().trim_end()),
+            Self::Uri(e) => write!(f, "{e}"),
+            Self::Rules(e) => write!(f, "{e}"),
+            Self::Defaults(e) => write!(f, "{e}"),
+            Self::Config(e) => write!(f, "{e}"),
+            Self::Io(p, io_err) => write!(f, "I/O error on {p:?}: {io_err}"),
+            Self::Missing(ids) => {
+                let ids = ids
+                    .iter()
+                    .map(|id| format!("#{id}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "No task found with ID: {ids}")
+            }
+            Self::Invalid(message) | Self::Conflict(message) | Self::Other(message) => {
+                write!(f, "{message}")
+            }
+            Self::Load(e, backup) => {
+                write!(f, "{e}")?;
+                if let Some(backup) = backup {
+                    write!(f, "\nThe previous version is kept in {backup:?}")?;
+                }
+                write!(f, "\nRun '{APPNAME} rescue' to repair the file")
+            }
+            Self::Unsaved => write!(f, "Changes were not saved"),
+        }
+    }
+}
+
+impl Error for TaskError {}
+
+impl From<StoreError> for TaskError {
+    fn from(e: StoreError) -> Self {
+        Self::Store(e)
+    }
+}
+
+impl From<DirError> for TaskError {
+    fn from(e: DirError) -> Self {
+        Self::Dir(e)
+    }
+}
+
+impl From<UriError> for TaskError {
+    fn from(e: UriError) -> Self {
+        Self::Uri(e)
+    }
+}
+
+impl From<RuleError> for TaskError {
+    fn from(e: RuleError) -> Self {
+        Self::Rules(e)
+    }
+}
+
+impl From<DefaultsError> for TaskError {
+    fn from(e: DefaultsError) -> Self {
+        Self::Defaults(e)
+    }
+}
+
+impl From<ConfigError> for TaskError {
+    fn from(e: ConfigError) -> Self {
+        Self::Config(e)
+    }
+}
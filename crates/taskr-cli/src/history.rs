@@ -0,0 +1,74 @@
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use time::OffsetDateTime;
+
+/// Setting this to anything but 0 opts in to recording invoked commands
+pub const HISTORY_ENV: &str = "TASKLY_HISTORY";
+pub const HISTORY_FILE: &str = "history.jsonl";
+/// Log of the auto-tagging rules applied to new tasks, always recorded
+pub const RULE_LOG_FILE: &str = "rules.jsonl";
+
+/// One recorded invocation, the arguments exclude the program name
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Entry {
+    #[serde(with = "time::serde::rfc3339")]
+    pub time: OffsetDateTime,
+    pub args: Vec<String>,
+}
+
+/// The auto-tagging rules that matched a new task
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RuleEntry {
+    #[serde(with = "time::serde::rfc3339")]
+    pub time: OffsetDateTime,
+    pub id: u64,
+    pub description: String,
+    pub rules: Vec<String>,
+}
+
+pub fn enabled() -> bool {
+    env::var_os(HISTORY_ENV).is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+pub fn record(path: &Path, args: Vec<String>, now: OffsetDateTime) -> io::Result<()> {
+    append(path, &Entry { time: now, args })
+}
+
+pub fn record_rules(path: &Path, entry: &RuleEntry) -> io::Result<()> {
+    append(path, entry)
+}
+
+/// Appends an entry as one JSON line, so recording never rewrites the whole file
+fn append<T: Serialize>(path: &Path, entry: &T) -> io::Result<()> {
+    let line = serde_json::to_string(&entry).expect("Failed to serialize history entry");
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{line}")
+}
+
+/// Recorded entries, oldest first. A missing file is an empty history and lines that do not
+/// parse, e.g. from an interrupted write, are skipped.
+pub fn read<T: DeserializeOwned>(path: &Path) -> io::Result<Vec<T>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(contents
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// The recorded commands, oldest first. Older versions logged applied rules here as entries
+/// without arguments, those are skipped.
+pub fn read_commands(path: &Path) -> io::Result<Vec<Entry>> {
+    let mut entries = read::<Entry>(path)?;
+    entries.retain(|entry| !entry.args.is_empty());
+    Ok(entries)
+}
@@ -0,0 +1,22 @@
+use std::process::{Command, Stdio};
+
+/// Seconds an HTTP check may take before it counts as not yet met
+const URL_TIMEOUT: &str = "10";
+
+/// Whether a GET request to `url` returns 200, checked with `curl`. An error means curl could
+/// not be run at all.
+pub fn url_ok(url: &str) -> Result<bool, String> {
+    let output = Command::new("curl")
+        .args(["--silent", "--location", "--output", "/dev/null"])
+        // Conditions are parsed as http or https already, redirects have to stay there too
+        .args(["--proto", "=http,https", "--proto-redir", "=http,https"])
+        .args(["--write-out", "%{http_code}", "--max-time", URL_TIMEOUT])
+        // Never read as an option, whatever the URL starts with
+        .args(["--", url])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| format!("could not run curl to check {url}: {e}"))?;
+    // Connection failures report 000, the server is just not up yet
+    Ok(String::from_utf8_lossy(&output.stdout).trim() == "200")
+}
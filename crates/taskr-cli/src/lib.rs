@@ -0,0 +1,14 @@
+pub mod cli;
+pub mod color;
+pub mod compat;
+pub mod error;
+pub mod history;
+pub mod http;
+pub mod limits;
+pub mod motd;
+pub mod quick;
+pub mod rescue;
+pub mod table;
+pub mod template;
+pub mod uri;
+pub mod utils;
@@ -0,0 +1,127 @@
+use std::env;
+
+use crate::utils::{APPNAME, Config};
+
+/// Overrides `max_open` in the config, the open tasks above which a warning suggests cleaning
+/// up. 0 turns the warning off.
+pub const MAX_OPEN_ENV: &str = "TASKLY_MAX_OPEN";
+/// Overrides `max_store_size` in the config, the size of tasks.json above which a warning
+/// suggests archiving, e.g. 512K or 2M. 0 turns the warning off.
+pub const MAX_SIZE_ENV: &str = "TASKLY_MAX_STORE_SIZE";
+
+const DEFAULT_MAX_OPEN: u64 = 200;
+const DEFAULT_MAX_SIZE: u64 = 1024 * 1024;
+
+/// Soft limits, crossing one only warns
+pub struct Limits {
+    pub max_open: u64,
+    pub max_size: u64,
+}
+
+impl Limits {
+    /// Reads the limits from the config, the environment overriding it. Variables that do not
+    /// parse are reported and the config or default is used instead.
+    pub fn load(config: &Config) -> (Self, Vec<String>) {
+        Self::resolve(config, |var| env::var(var).ok())
+    }
+
+    fn resolve(config: &Config, var: impl Fn(&str) -> Option<String>) -> (Self, Vec<String>) {
+        let mut problems = Vec::new();
+        let mut read = |name: &str, configured: Option<u64>, default: u64| {
+            let fallback = configured.unwrap_or(default);
+            match var(name) {
+                Some(value) => parse_size(&value).unwrap_or_else(|| {
+                    problems.push(format!(
+                        "ignoring {name}={value}, expected a number such as 500 or 2M"
+                    ));
+                    fallback
+                }),
+                None => fallback,
+            }
+        };
+        let limits = Self {
+            max_open: read(MAX_OPEN_ENV, config.max_open, DEFAULT_MAX_OPEN),
+            max_size: read(MAX_SIZE_ENV, config.max_store_size, DEFAULT_MAX_SIZE),
+        };
+        (limits, problems)
+    }
+
+    /// Warnings for the limits that `open` tasks and a store of `size` bytes cross
+    pub fn check(&self, open: u64, size: u64) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.max_open > 0 && open > self.max_open {
+            warnings.push(format!(
+                "{open} open tasks, more than max_open allows ({}). Completing, deleting or \
+                 moving some to someday keeps the list manageable",
+                self.max_open
+            ));
+        }
+        if self.max_size > 0 && size > self.max_size {
+            warnings.push(format!(
+                "tasks.json is {}, larger than max_store_size allows ({}). Run '{APPNAME} \
+                 archive' to move completed tasks out",
+                format_size(size),
+                format_size(self.max_size)
+            ));
+        }
+        warnings
+    }
+}
+
+/// A number with an optional K, M or G suffix in powers of 1024
+pub fn parse_size(s: &str) -> Option<u64> {
+    let s = s.trim();
+    let (number, unit) = match s.char_indices().last()? {
+        (i, c) if c.is_ascii_alphabetic() => (&s[..i], c.to_ascii_uppercase()),
+        _ => (s, 'B'),
+    };
+    let scale = match unit {
+        'B' => 1,
+        'K' => 1024,
+        'M' => 1024 * 1024,
+        'G' => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    number.trim().parse::<u64>().ok()?.checked_mul(scale)
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{bytes} B"),
+        1024..1048576 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MiB", bytes as f64 / 1048576.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn environment_overrides_config() {
+        let config = Config {
+            max_open: Some(50),
+            max_store_size: Some(2048),
+            ..Config::default()
+        };
+        let (limits, problems) = Limits::resolve(&config, |_| None);
+        assert_eq!((limits.max_open, limits.max_size), (50, 2048));
+        assert!(problems.is_empty());
+
+        let (limits, _) = Limits::resolve(&config, |var| {
+            (var == MAX_OPEN_ENV).then(|| "10".to_string())
+        });
+        assert_eq!((limits.max_open, limits.max_size), (10, 2048));
+    }
+
+    #[test]
+    fn invalid_variables_fall_back_to_config() {
+        let config = Config {
+            max_open: Some(50),
+            ..Config::default()
+        };
+        let (limits, problems) = Limits::resolve(&config, |_| Some("lots".to_string()));
+        assert_eq!((limits.max_open, limits.max_size), (50, DEFAULT_MAX_SIZE));
+        assert_eq!(problems.len(), 2);
+    }
+}
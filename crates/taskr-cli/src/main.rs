@@ -0,0 +1,2430 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    env, fs,
+    io::{self, IsTerminal, Read, Write},
+    iter,
+    ops::{Range, RangeInclusive},
+    path::{self, Path},
+    process, slice,
+    sync::OnceLock,
+};
+
+use clap::{Parser, ValueEnum};
+use serde::Serialize;
+use taskr_cli::{
+    cli::{
+        Cli, ColorMode, Commands, ConfigCommand, ContextCommand, ExportFormat, ImportFormat,
+        Output, ProjectCommand, TagAction, TmpCommand,
+    },
+    color::{self, Style, Theme},
+    compat,
+    error::{EXIT_FAILURE, TaskError},
+    history, http,
+    limits::Limits,
+    motd, quick,
+    rescue::Rescue,
+    table::{self, Table},
+    template, uri,
+    utils::{self, APPNAME, Config, DirError, Dirs},
+};
+use taskr_core::{
+    condition::Condition,
+    defaults::{self, Defaults},
+    diff::{self, ChangedTask, DiffTask, FieldChange, StoreDiff},
+    generate, integrity, journal, parse,
+    pattern::Pattern,
+    persist, profile,
+    query::{self, SortKey as Sort},
+    rules,
+    store::{BLOCKED, Note, StoreError, Task, TaskStatus, TaskStore},
+};
+use taskr_sync::{
+    export::{self, JsonTask},
+    taskwarrior, todotxt,
+};
+use time::{
+    Duration, OffsetDateTime,
+    format_description::{self, well_known::Rfc3339},
+};
+
+const SOMEDAY_REVIEW_INTERVAL: Duration = Duration::days(30);
+
+/// Format of times shown to the user, from the config. It was validated when loaded.
+static DATE_FORMAT: OnceLock<String> = OnceLock::new();
+/// Styles for text output, only set when it is colored
+static THEME: OnceLock<Theme> = OnceLock::new();
+/// Whether tasks from an earlier due day show as rolled over rather than overdue, from the config
+static ROLLOVER: OnceLock<bool> = OnceLock::new();
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("{e}");
+        process::exit(e.exit_code());
+    }
+}
+
+fn run() -> Result<(), TaskError> {
+    let time = OffsetDateTime::now_local().unwrap_or_else(|e| {
+        eprintln!("Failed to get local time offset: {e}");
+        eprintln!("Falling back to UTC");
+        OffsetDateTime::now_utc()
+    });
+
+    let (args, notices) = compat::rewrite(env::args_os().collect());
+    for notice in notices {
+        eprintln!("Warning: {notice}");
+    }
+    let cli = Cli::parse_from(args);
+    let output = cli.output;
+    if output == Output::Json
+        && let Some(cmd) = &cli.command
+        && !cmd.supports_json()
+    {
+        return Err(TaskError::Invalid(
+            "This command has no JSON output, rescue is interactive and export has its own \
+             --format"
+                .to_string(),
+        ));
+    }
+
+    // Needs neither the settings it edits nor the store, so a broken config can still be fixed
+    let config_path = utils::config_path()?;
+    if let Some(Commands::Config { command }) = &cli.command {
+        return configure(&config_path, command, output);
+    }
+    let config = utils::load_config(&config_path)?;
+    if let Some(format) = &config.date_format {
+        DATE_FORMAT
+            .set(format.clone())
+            .expect("The date format is only set once");
+    }
+    let color = cli.color.or(config.color).unwrap_or(ColorMode::Auto);
+    if output == Output::Text && color::enabled(color) {
+        THEME
+            .set(config.theme.clone())
+            .expect("The theme is only set once");
+    }
+    ROLLOVER
+        .set(config.rollover.unwrap_or(false))
+        .expect("Rollover is only set once");
+
+    let data_dir = utils::get_data_dir(cli.data_dir.as_deref(), &config)?;
+    if let Some(Commands::Context { command }) = &cli.command {
+        return switch_context(&data_dir, command, output);
+    }
+    // A typo in the flag should not quietly start an empty profile
+    if let Some(name) = &cli.profile
+        && !profile::dir(&data_dir, name).exists()
+    {
+        return Err(TaskError::Invalid(format!(
+            "No profile named {name}, create it with '{APPNAME} context switch {name}'"
+        )));
+    }
+    let profile = cli
+        .profile
+        .clone()
+        .unwrap_or_else(|| profile::active(&data_dir));
+    let taskly_state = profile::dir(&data_dir, &profile);
+    fs::create_dir_all(&taskly_state).map_err(|e| TaskError::Io(taskly_state.clone(), e))?;
+    let tasks_filepath = taskly_state.join("tasks.json");
+    let archive_filepath = taskly_state.join("archive.json");
+
+    // Typing can take a while, other invocations should not wait on the lock meanwhile
+    let quick_entry = match &cli.command {
+        Some(Commands::Quick) => match read_quick_entry()? {
+            Some(description) => Some(description),
+            None => return Ok(()),
+        },
+        _ => None,
+    };
+
+    // Runs in every new shell, so a throttled summary returns before the store is even loaded
+    let motd_file = taskly_state.join(motd::MOTD_FILE);
+    if let Some(Commands::Motd { force: false }) = &cli.command
+        && motd::last_shown(&motd_file).is_some_and(|last| time - last < motd::THROTTLE)
+    {
+        return Ok(());
+    }
+
+    // Held until exit so concurrent invocations cannot interleave their read-modify-write cycles
+    let lock = lock_store(&tasks_filepath)?;
+
+    // Works on the raw file, so it has to run before the normal load that is failing
+    if let Some(Commands::Rescue) = &cli.command {
+        return rescue(&tasks_filepath);
+    }
+
+    let backup = persist::backup_path(&tasks_filepath);
+    let (mut store, kept) = TaskStore::open_verified(&tasks_filepath)
+        .map_err(|e| TaskError::Load(e, backup.exists().then(|| backup.clone())))?;
+    if kept.is_some() || store.checksum_mismatch() {
+        eprintln!(
+            "Warning: tasks.json does not match its checksum, it may have been partially \
+             written or modified outside taskly"
+        );
+    }
+    if let Some(kept) = kept {
+        eprintln!("Restored the previous version from {backup:?}, the file is kept as {kept:?}");
+    } else if store.checksum_mismatch() && backup.exists() {
+        eprintln!(
+            "The previous version is kept in {backup:?}, run '{APPNAME} rescue' to restore it"
+        );
+    }
+
+    let invocation = env::args_os()
+        .skip(1)
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    // Undo and redo expect the tasks as their operations left them, so they sweep nothing
+    let replaying = matches!(
+        cli.command,
+        Some(Commands::Undo { .. } | Commands::Redo { .. })
+    );
+    if store.exists() && !replaying {
+        clear_temporary(
+            &mut store,
+            |task| task.expires.is_some_and(|e| e <= time),
+            true,
+        )?;
+    }
+    store.set_operation(format_command(&invocation), time);
+    // Only commands that give tasks tags or a project need them, so a broken defaults file does
+    // not stop the others. Imports keep what they bring along.
+    let defaults = if matches!(
+        cli.command,
+        Some(
+            Commands::Add { .. } | Commands::Quick | Commands::Update { .. } | Commands::Tag { .. }
+        )
+    ) {
+        load_defaults()?
+    } else {
+        Defaults::default()
+    };
+
+    let history_file = taskly_state.join(history::HISTORY_FILE);
+    let rule_log = taskly_state.join(history::RULE_LOG_FILE);
+    let recorded = cli.command.as_ref().is_some_and(|cmd| {
+        !matches!(
+            cmd,
+            Commands::History { .. } | Commands::Again | Commands::Motd { .. }
+        )
+    });
+    if recorded
+        && history::enabled()
+        && let Err(e) = history::record(&history_file, invocation, time)
+    {
+        eprintln!("Failed to record command history: {e}");
+    }
+
+    let Some(cmd) = &cli.command else {
+        return Ok(());
+    };
+    // Commands without output of their own report the IDs they touched for scripts
+    let before = (output == Output::Json && cmd.reports_changes()).then(|| store.tasks().clone());
+    let result = 'command: {
+        match cmd {
+            Commands::Add {
+                description,
+                file,
+                template,
+                vars,
+                due,
+                remind,
+                priority,
+                tags,
+                project,
+                parent,
+                repeat,
+                no_rules,
+            } => {
+                let (description, note) = match (description.as_deref(), file) {
+                    (Some("-"), _) => {
+                        let mut input = String::new();
+                        io::stdin()
+                            .read_to_string(&mut input)
+                            .map_err(|e| TaskError::Io("<stdin>".into(), e))?;
+                        split_description(&input)
+                    }
+                    (Some(description), _) => (description.to_string(), None),
+                    (None, Some(path)) => {
+                        let input =
+                            fs::read_to_string(path).map_err(|e| TaskError::Io(path.clone(), e))?;
+                        split_description(&input)
+                    }
+                    (None, None) => {
+                        let name = template
+                            .as_deref()
+                            .expect("clap requires a description, --file or --template");
+                        split_description(&fill_template(name, vars)?)
+                    }
+                };
+                if description.is_empty() {
+                    return Err(TaskError::Invalid(
+                        "Description is empty, nothing to add".to_string(),
+                    ));
+                }
+
+                warn_unknown_references(&store, &description);
+
+                let mut task = Task::new(description, time);
+                if let Some(text) = note {
+                    task.notes.push(Note { time, text });
+                }
+                task.due = *due;
+                task.remind = *remind;
+                task.priority = *priority;
+                for tag in tags {
+                    if !task.has_tag(tag) {
+                        task.tags.push(tag.clone());
+                    }
+                }
+                task.project = project.clone();
+                if let Some(parent) = parent {
+                    if store.get(*parent).is_none() {
+                        return Err(StoreError::NotFound(*parent).into());
+                    }
+                    task.parent = Some(*parent);
+                }
+                task.repeat = *repeat;
+                let rules = if *no_rules {
+                    Vec::new()
+                } else {
+                    apply_rules(&mut task, output)?
+                };
+                apply_defaults(&defaults, &mut task, time, output);
+                let id = store.insert(task);
+
+                save_store(&mut store)?;
+                log_rules(&rule_log, &store, id, rules, time);
+                print_created(&store, id, output);
+            }
+            Commands::Quick => {
+                let description = quick_entry.expect("Quick entry is read before locking");
+                warn_unknown_references(&store, &description);
+
+                let mut task = Task::new(description, time);
+                let rules = apply_rules(&mut task, output)?;
+                apply_defaults(&defaults, &mut task, time, output);
+                let id = store.insert(task);
+
+                save_store(&mut store)?;
+                log_rules(&rule_log, &store, id, rules, time);
+                match output {
+                    Output::Text => println!("Created task {id}"),
+                    _ => print_created(&store, id, output),
+                }
+            }
+            Commands::Update {
+                id,
+                description,
+                due,
+                no_due,
+                remind,
+                no_remind,
+                tags,
+                project,
+                no_project,
+                repeat,
+                no_repeat,
+            } => {
+                if !store.exists() {
+                    note(output, "No tasks found, start create one first");
+                    break 'command Ok(());
+                }
+                if store.get(*id).is_none() {
+                    return Err(StoreError::NotFound(*id).into());
+                }
+
+                if let Some(description) = description {
+                    warn_unknown_references(&store, description);
+                    store
+                        .update(*id, description.to_string(), time)
+                        .expect("Task exists");
+                }
+                if due.is_some() || *no_due {
+                    store.set_due(*id, *due, time).expect("Task exists");
+                }
+                if remind.is_some() || *no_remind {
+                    store.set_remind(*id, *remind, time).expect("Task exists");
+                }
+                let mut gained_tags = Vec::new();
+                for tag in tags {
+                    if store.add_tag(*id, tag, time).expect("Task exists") {
+                        gained_tags.push(tag.clone());
+                    }
+                }
+                let mut gained_project = None;
+                if project.is_some() || *no_project {
+                    let previous = store.get(*id).and_then(|task| task.project.clone());
+                    if *project != previous {
+                        gained_project = project.as_deref();
+                    }
+                    store
+                        .set_project(*id, project.clone(), time)
+                        .expect("Task exists");
+                }
+                apply_stored_defaults(
+                    &mut store,
+                    &defaults,
+                    *id,
+                    &gained_tags,
+                    gained_project,
+                    time,
+                    output,
+                )?;
+                if repeat.is_some() || *no_repeat {
+                    store.set_repeat(*id, *repeat, time).expect("Task exists");
+                }
+
+                save_store(&mut store)?;
+            }
+            Commands::Delete { ids, cascade, yes } => {
+                if !store.exists() {
+                    note(output, "No tasks found, start create one first");
+                    break 'command Ok(());
+                }
+
+                let ids = expand_ids(ids);
+                let (ids, missing): (Vec<_>, Vec<_>) =
+                    ids.into_iter().partition(|id| store.get(*id).is_some());
+
+                if !*yes && !ids.is_empty() && !confirm_delete(&store, &ids)? {
+                    note(output, "Nothing deleted");
+                    break 'command Ok(());
+                }
+
+                let mut deleted = Vec::new();
+                let mut with_subtasks = Vec::new();
+                for id in ids {
+                    // Already gone as a subtask of an earlier ID
+                    if store.get(id).is_none() {
+                        continue;
+                    }
+
+                    let referrers = store.backlinks(id);
+                    if !referrers.is_empty() {
+                        eprintln!(
+                            "Warning: task {id} is still referenced by {}",
+                            format_ids(&referrers)
+                        );
+                    }
+
+                    if *cascade {
+                        deleted.extend(store.delete_cascade(id)?);
+                    } else {
+                        match store.delete(id) {
+                            Ok(_) => deleted.push(id),
+                            Err(StoreError::HasSubtasks(..)) => with_subtasks.push(id),
+                            Err(e) => return Err(e.into()),
+                        }
+                    }
+                }
+
+                if !deleted.is_empty() {
+                    save_store(&mut store)?;
+                    deleted.sort_unstable();
+                    note(output, &format!("Deleted {}", format_ids(&deleted)));
+                }
+                let skipped = (!with_subtasks.is_empty()).then(|| {
+                    format!(
+                        "Skipped {}, which still have subtasks. Delete those first, or pass \
+                     --cascade to delete them too",
+                        format_ids(&with_subtasks)
+                    )
+                });
+                break 'command bulk_result(missing, skipped);
+            }
+            Commands::List {
+                status,
+                all,
+                changed_since,
+                sort,
+                reverse,
+                long,
+                tags,
+                project,
+                archived,
+            } => {
+                let archive;
+                let store = if *archived {
+                    archive = open_archive(&archive_filepath)?;
+                    &archive
+                } else {
+                    &store
+                };
+
+                // Looks across every status unless one is given, a standup cares about what was
+                // finished too
+                if let Some(since) = changed_since {
+                    let mut tasks = store
+                        .list(status.as_ref())
+                        .into_iter()
+                        .filter(|(_, task)| !task.is_temporary() && task.updated >= *since)
+                        .collect::<Vec<_>>();
+                    query::filter_tasks(&mut tasks, store, tags, project.as_deref())?;
+                    query::sort_tasks(&mut tasks, sort.map_or(Sort::Updated, Sort::from), *reverse);
+
+                    if tasks.is_empty() && output == Output::Text {
+                        println!("No tasks changed since {}", format_time(*since));
+                    }
+                    list_tasks(&tasks, store, time, *long, output);
+                    break 'command Ok(());
+                }
+
+                let mut tasks = if *all || *archived {
+                    store.list(None)
+                } else {
+                    let status = status.clone().or_else(|| config.status.clone());
+                    store.list(Some(&status.unwrap_or(TaskStatus::Todo)))
+                };
+                query::filter_tasks(&mut tasks, store, tags, project.as_deref())?;
+                let sort = sort.or(config.sort).map_or(Sort::Id, Sort::from);
+                query::sort_tasks(&mut tasks, sort, *reverse);
+                list_tasks(&tasks, store, time, *long, output);
+            }
+            Commands::Search {
+                query,
+                regex,
+                status,
+                sort,
+                reverse,
+                long,
+                tags,
+                project,
+                archived,
+            } => {
+                let pattern = if *regex {
+                    Pattern::new(query, true)
+                        .map_err(|e| TaskError::Invalid(format!("Invalid regex '{query}': {e}")))?
+                } else {
+                    Pattern::literal(query, true)
+                };
+
+                let archive;
+                let store = if *archived {
+                    archive = open_archive(&archive_filepath)?;
+                    &archive
+                } else {
+                    &store
+                };
+
+                let highlight = THEME.get().is_some();
+                let mut tasks = store.list(status.as_ref());
+                query::filter_tasks(&mut tasks, store, tags, project.as_deref())?;
+                let matches = tasks
+                    .into_iter()
+                    .filter_map(|(id, task)| {
+                        Some((id, highlight_match(task, &pattern, highlight)?))
+                    })
+                    .collect::<Vec<_>>();
+                if matches.is_empty() && output == Output::Text {
+                    println!("No tasks match '{query}'");
+                    break 'command Ok(());
+                }
+
+                let mut tasks = matches
+                    .iter()
+                    .map(|(id, task)| (*id, task))
+                    .collect::<Vec<_>>();
+                query::sort_tasks(&mut tasks, sort.map_or(Sort::Id, Sort::from), *reverse);
+                list_tasks(&tasks, store, time, *long, output);
+            }
+            Commands::Status { ids, status, force } => {
+                if !store.exists() {
+                    note(output, "No tasks found, start create one first");
+                    break 'command Ok(());
+                }
+
+                let (missing, skipped) =
+                    set_statuses(&mut store, &expand_ids(ids), status, *force, time, output)?;
+                break 'command bulk_result(missing, skipped);
+            }
+            Commands::Done { ids, force } => {
+                if !store.exists() {
+                    note(output, "No tasks found, start create one first");
+                    break 'command Ok(());
+                }
+
+                let ids = expand_ids(ids);
+                let (missing, skipped) = set_statuses(
+                    &mut store,
+                    &ids,
+                    &TaskStatus::Complete,
+                    *force,
+                    time,
+                    output,
+                )?;
+                break 'command bulk_result(missing, skipped);
+            }
+            Commands::Undone { ids } => {
+                if !store.exists() {
+                    note(output, "No tasks found, start create one first");
+                    break 'command Ok(());
+                }
+
+                let mut reopened = Vec::new();
+                let mut unchanged = Vec::new();
+                let mut missing = Vec::new();
+                for id in expand_ids(ids) {
+                    match store.get(id) {
+                        None => missing.push(id),
+                        Some(task) if task.status != TaskStatus::Complete => unchanged.push(id),
+                        Some(_) => {
+                            store.set_status(id, TaskStatus::Todo, time)?;
+                            reopened.push(id);
+                        }
+                    }
+                }
+
+                if !reopened.is_empty() {
+                    save_store(&mut store)?;
+                    note(output, &format!("Reopened {}", format_ids(&reopened)));
+                }
+                if !unchanged.is_empty() {
+                    note(output, &format!("Not complete: {}", format_ids(&unchanged)));
+                }
+                break 'command bulk_result(missing, None);
+            }
+            Commands::Priority { id, level } => {
+                if !store.exists() {
+                    note(output, "No tasks found, start create one first");
+                    break 'command Ok(());
+                }
+
+                store.set_priority(*id, *level, time)?;
+
+                save_store(&mut store)?;
+            }
+            Commands::Project { command } => match command {
+                ProjectCommand::List => {
+                    if store.projects().is_empty() && output == Output::Text {
+                        println!("No projects yet, create one with 'project add <name>'");
+                    }
+                    let mut counts = Vec::new();
+                    for project in store.projects() {
+                        let tasks = store
+                            .list(None)
+                            .into_iter()
+                            .filter(|(_, task)| task.project.as_ref() == Some(project))
+                            .collect::<Vec<_>>();
+                        let open = tasks
+                            .iter()
+                            .filter(|(_, task)| task.status != TaskStatus::Complete)
+                            .count();
+                        counts.push(ProjectCount {
+                            name: project,
+                            open,
+                            total: tasks.len(),
+                        });
+                    }
+
+                    match output {
+                        Output::Text => {
+                            for ProjectCount { name, open, total } in counts {
+                                println!("{name} ({open} open, {total} total)");
+                            }
+                        }
+                        Output::Json => print_json(&counts),
+                        Output::Plain => {
+                            for ProjectCount { name, open, total } in counts {
+                                println!("{name}\t{open}\t{total}");
+                            }
+                        }
+                    }
+                }
+                ProjectCommand::Add { name } => {
+                    if !store.add_project(name) {
+                        return Err(StoreError::ProjectExists(name.clone()).into());
+                    }
+                    save_store(&mut store)?;
+                }
+                ProjectCommand::Rename { old, new } => {
+                    let moved = store.rename_project(old, new, time)?;
+                    save_store(&mut store)?;
+                    note(
+                        output,
+                        &format!("Renamed project {old} to {new}, {moved} tasks moved"),
+                    );
+                }
+                ProjectCommand::Delete { name } => {
+                    let moved = store.delete_project(name, time)?;
+                    save_store(&mut store)?;
+                    note(
+                        output,
+                        &format!("Deleted project {name}, {moved} tasks no longer have a project"),
+                    );
+                }
+            },
+            Commands::Tmp { command } => match command {
+                TmpCommand::Add { description, ttl } => {
+                    let expires = time.checked_add(*ttl).ok_or_else(|| {
+                        TaskError::Invalid(
+                            "The --ttl is too long, it ends past the latest supported date"
+                                .to_string(),
+                        )
+                    })?;
+                    let mut task = Task::new(description.clone(), time);
+                    task.session = Some(utils::session_id());
+                    task.expires = Some(expires);
+                    let id = store.insert(task);
+                    save_store(&mut store)?;
+                    match output {
+                        Output::Text => println!(
+                            "Created temporary task {id}, expires {}",
+                            format_time(expires)
+                        ),
+                        _ => print_created(&store, id, output),
+                    }
+                }
+                TmpCommand::List { all } => {
+                    let session = utils::session_id();
+                    let mut tasks = store
+                        .list(None)
+                        .into_iter()
+                        .filter(|(_, task)| {
+                            task.session.as_ref().is_some_and(|s| *all || *s == session)
+                        })
+                        .collect::<Vec<_>>();
+                    if tasks.is_empty() && output == Output::Text {
+                        println!("No temporary tasks");
+                    }
+                    query::sort_tasks(&mut tasks, Sort::Id, false);
+                    list_tasks(&tasks, &store, time, false, output);
+                }
+                TmpCommand::Clear { all, expired } => {
+                    let session = utils::session_id();
+                    let cleared = clear_temporary(
+                        &mut store,
+                        |task| match (all, expired) {
+                            (true, _) => true,
+                            (_, true) => task.expires.is_some_and(|e| e <= time),
+                            _ => task.session.as_ref() == Some(&session),
+                        },
+                        false,
+                    )?;
+                    if cleared.is_empty() {
+                        note(output, "No temporary tasks to clear");
+                    } else {
+                        note(output, &format!("Deleted {}", format_ids(&cleared)));
+                    }
+                }
+            },
+            Commands::Today { long } => {
+                let end_of_today = parse::parse_due("today").expect("today is a valid due date");
+                let mut tasks = store
+                    .list(None)
+                    .into_iter()
+                    .filter(|(_, task)| {
+                        task.status.is_open()
+                            && !task.is_temporary()
+                            && task.due.is_some_and(|due| due <= end_of_today)
+                    })
+                    .collect::<Vec<_>>();
+                if tasks.is_empty() && output == Output::Text {
+                    println!("Nothing due today");
+                }
+                query::sort_tasks(&mut tasks, Sort::Due, false);
+                list_tasks(&tasks, &store, time, *long, output);
+            }
+            Commands::Motd { force: _ } => {
+                if !store.exists() {
+                    break 'command Ok(());
+                }
+
+                let end_of_today = parse::parse_due("today").expect("today is a valid due date");
+                let last = motd::last_shown(&motd_file);
+                let mut summary = motd::summary(&store, last, end_of_today, time).to_vec();
+                for (id, task) in motd::reminders(&store, time) {
+                    let due = task.due.expect("Tasks with a reminder are due");
+                    summary.push(format!(
+                        "Reminder: {} (#{id}) is due {}",
+                        task.description,
+                        format_time(due)
+                    ));
+                }
+                if output == Output::Json {
+                    print_json(&summary);
+                } else {
+                    for line in summary {
+                        println!("{line}");
+                    }
+                }
+                if let Err(e) = motd::record_shown(&motd_file, time) {
+                    eprintln!("Failed to record when the summary was shown: {e}");
+                }
+            }
+            Commands::Show { id } => {
+                let task = store.get(*id).ok_or(StoreError::NotFound(*id))?;
+                match output {
+                    Output::Text => print_task(*id, task, &store, time, ""),
+                    Output::Json => print_json(&JsonTask { id: *id, task }),
+                    Output::Plain => print_plain(*id, task),
+                }
+            }
+            Commands::Note { id, text } => {
+                let text = text.trim();
+                if text.is_empty() {
+                    return Err(TaskError::Invalid(
+                        "Note is empty, nothing to add".to_string(),
+                    ));
+                }
+
+                store.add_note(*id, text.to_string(), time)?;
+                warn_unknown_references(&store, text);
+                save_store(&mut store)?;
+                note(output, &format!("Added a note to task {id}"));
+            }
+            Commands::Tag { id, action, tag } => {
+                if !store.exists() {
+                    note(output, "No tasks found, start create one first");
+                    break 'command Ok(());
+                }
+
+                let changed = match action {
+                    TagAction::Add => store.add_tag(*id, tag, time),
+                    TagAction::Remove => store.remove_tag(*id, tag, time),
+                };
+                if changed? {
+                    if let TagAction::Add = action {
+                        apply_stored_defaults(
+                            &mut store,
+                            &defaults,
+                            *id,
+                            slice::from_ref(tag),
+                            None,
+                            time,
+                            output,
+                        )?;
+                    }
+                    save_store(&mut store)?;
+                } else {
+                    match action {
+                        TagAction::Add => {
+                            note(output, &format!("Task {id} is already tagged {tag}"))
+                        }
+                        TagAction::Remove => {
+                            note(output, &format!("Task {id} is not tagged {tag}"))
+                        }
+                    }
+                }
+            }
+            Commands::Depend { id, on } => {
+                if !store.exists() {
+                    note(output, "No tasks found, start create one first");
+                    break 'command Ok(());
+                }
+                if id == on {
+                    return Err(TaskError::Invalid(
+                        "A task cannot depend on itself".to_string(),
+                    ));
+                }
+
+                if store.add_dependency(*id, *on, time)? {
+                    save_store(&mut store)?;
+                } else {
+                    note(output, &format!("Task {id} already depends on task {on}"));
+                }
+            }
+            Commands::Move { id, before, after } => {
+                let (other, after) = match (before, after) {
+                    (Some(other), _) => (*other, false),
+                    (None, Some(other)) => (*other, true),
+                    (None, None) => unreachable!("clap requires --before or --after"),
+                };
+                if other == *id {
+                    return Err(TaskError::Invalid(
+                        "Cannot move a task relative to itself".to_string(),
+                    ));
+                }
+
+                store.move_task(*id, other, after)?;
+
+                save_store(&mut store)?;
+            }
+            Commands::Someday { id } => {
+                if !store.exists() {
+                    note(output, "No tasks found, start create one first");
+                    break 'command Ok(());
+                }
+
+                store.set_status(*id, TaskStatus::Someday, time)?;
+                // Parking a task counts as looking at it, it comes up again next cycle
+                store
+                    .mark_reviewed(*id, time)
+                    .expect("Task exists, its status was just set");
+
+                save_store(&mut store)?;
+                note(output, &format!("Moved task {id} to someday"));
+            }
+            Commands::Block {
+                id,
+                after,
+                file,
+                url,
+            } => {
+                let conditions = after
+                    .map(Condition::After)
+                    .into_iter()
+                    // Maintenance runs from anywhere, e.g. cron, so the path must not be relative
+                    .chain(file.as_ref().map(|file| {
+                        Condition::File(path::absolute(file).unwrap_or_else(|_| file.clone()))
+                    }))
+                    .chain(url.clone().map(Condition::Url))
+                    .collect::<Vec<_>>();
+                let waiting_on = conditions
+                    .iter()
+                    .map(Condition::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                store.block(*id, conditions, time)?;
+
+                save_store(&mut store)?;
+                note(output, &format!("Blocked task {id} until {waiting_on}"));
+            }
+            Commands::Maintain => {
+                let blocked = TaskStatus::Other(BLOCKED.into());
+                let waiting = store
+                    .list(Some(&blocked))
+                    .into_iter()
+                    .filter(|(_, task)| !task.waiting_on.is_empty())
+                    .map(|(id, task)| (id, task.clone()))
+                    .collect::<Vec<_>>();
+
+                let mut unblocked = Vec::new();
+                for (id, task) in waiting {
+                    let mut met = true;
+                    for condition in &task.waiting_on {
+                        match condition.is_met(time, http::url_ok) {
+                            Ok(true) => {}
+                            Ok(false) => met = false,
+                            Err(e) => {
+                                eprintln!("Warning: task {id}: {e}");
+                                met = false;
+                            }
+                        }
+                    }
+                    if met {
+                        store.unblock(id, time)?;
+                        unblocked.push((id, task.description));
+                    }
+                }
+
+                if unblocked.is_empty() {
+                    note(output, "No blocked tasks are ready");
+                    break 'command Ok(());
+                }
+                save_store(&mut store)?;
+                for (id, description) in unblocked {
+                    note(output, &format!("Unblocked task {id}: {description}"));
+                }
+            }
+            Commands::Archive { older_than } => {
+                let cutoff = older_than
+                    .map(|age| {
+                        time.checked_sub(age).ok_or_else(|| {
+                            TaskError::Invalid("--older-than reaches back too far".to_string())
+                        })
+                    })
+                    .transpose()?;
+                let candidates = store
+                    .list(Some(&TaskStatus::Complete))
+                    .into_iter()
+                    .filter(|(_, task)| cutoff.is_none_or(|cutoff| task.updated <= cutoff))
+                    .map(|(id, _)| id)
+                    .collect::<BTreeSet<_>>();
+                // A task stays while anything below it does, so the tree is never split
+                let (kept, archived): (Vec<u64>, Vec<u64>) = candidates.iter().partition(|id| {
+                    store
+                        .descendants(**id)
+                        .iter()
+                        .any(|descendant| !candidates.contains(descendant))
+                });
+                if archived.is_empty() {
+                    note(output, "No completed tasks to archive");
+                } else {
+                    let mut archive = open_archive(&archive_filepath)?;
+                    for &id in &archived {
+                        let task = store.remove(id).expect("Archived task was just listed");
+                        archive.restore(id, task);
+                        store.mark_archived(id);
+                    }
+                    // Saved first, if the task list fails to save the tasks are in both files
+                    // rather than in neither
+                    save_store(&mut archive)?;
+                    save_store(&mut store)?;
+                    note(output, &format!("Archived {}", format_ids(&archived)));
+                }
+                if !kept.is_empty() {
+                    note(
+                        output,
+                        &format!(
+                            "Kept {}, their subtasks are not all completed",
+                            format_ids(&kept)
+                        ),
+                    );
+                }
+            }
+            Commands::Unarchive { id } => {
+                let mut archive = open_archive(&archive_filepath)?;
+                if store.get(*id).is_some() {
+                    return Err(TaskError::Conflict(format!(
+                        "Cannot unarchive task {id}, the task list already has a task {id}"
+                    )));
+                }
+                let task = archive.remove(*id)?;
+                store.restore(*id, task);
+                store.mark_archived(*id);
+                save_store(&mut store)?;
+                save_store(&mut archive)?;
+                note(output, &format!("Unarchived task {id}"));
+            }
+            Commands::Review { someday: _ } => {
+                let someday = store.list(Some(&TaskStatus::Someday));
+                let mut due = someday
+                    .iter()
+                    .filter(|(_, task)| {
+                        task.reviewed
+                            .is_none_or(|reviewed| time - reviewed >= SOMEDAY_REVIEW_INTERVAL)
+                    })
+                    .map(|(id, task)| (*id, task.description.clone()))
+                    .collect::<Vec<_>>();
+                due.sort_unstable();
+
+                if due.is_empty() {
+                    let next_review = someday
+                        .iter()
+                        .filter_map(|(_, task)| task.reviewed)
+                        .min()
+                        .map(|reviewed| reviewed + SOMEDAY_REVIEW_INTERVAL);
+                    match next_review {
+                        Some(next) => note(
+                            output,
+                            &format!(
+                                "Nothing to review, next someday review is on {}",
+                                format_time(next)
+                            ),
+                        ),
+                        None => note(output, "No someday tasks to review"),
+                    }
+                    break 'command Ok(());
+                }
+
+                // Without a terminal nobody answers, so nothing counts as reviewed
+                if !io::stdin().is_terminal() {
+                    for (id, description) in due {
+                        note(output, &format!("{id}: {description}"));
+                    }
+                    eprintln!("Run '{APPNAME} review --someday' in a terminal to review these");
+                    break 'command Ok(());
+                }
+
+                for (id, description) in due {
+                    note(output, &format!("{id}: {description}"));
+
+                    let action = prompt("[k]eep, [a]ctivate, [c]omplete or [s]kip? ");
+
+                    let status = match action.as_str() {
+                        "a" | "activate" => Some(TaskStatus::Todo),
+                        "c" | "complete" => Some(TaskStatus::Complete),
+                        "s" | "skip" => continue,
+                        _ => None,
+                    };
+                    if let Some(status) = status {
+                        let next = store
+                            .set_status(id, status, time)
+                            .expect("Task vanished mid-review");
+                        if let Some(next) = next {
+                            print_next_occurrence(&store, next, output);
+                        }
+                    }
+                    store
+                        .mark_reviewed(id, time)
+                        .expect("Task vanished mid-review");
+                }
+
+                save_store(&mut store)?;
+            }
+            Commands::Standup { since, markdown } => {
+                let done = store
+                    .list(Some(&TaskStatus::Complete))
+                    .into_iter()
+                    .filter(|(_, task)| task.updated >= *since)
+                    .collect::<Vec<_>>();
+                let in_progress = store.list(Some(&TaskStatus::InProgress));
+                let blocked = store.list(Some(&TaskStatus::Other(BLOCKED.into())));
+
+                let mut sections = [
+                    ("Yesterday", done),
+                    ("Today", in_progress),
+                    ("Blockers", blocked),
+                ];
+                for (_, tasks) in &mut sections {
+                    tasks.sort_by_key(|(id, _)| *id);
+                }
+                if output == Output::Json {
+                    let [done, in_progress, blocked] = sections.map(|(_, tasks)| {
+                        tasks
+                            .into_iter()
+                            .map(|(id, task)| JsonTask { id, task })
+                            .collect::<Vec<_>>()
+                    });
+                    print_json(&Standup {
+                        yesterday: done,
+                        today: in_progress,
+                        blockers: blocked,
+                    });
+                    break 'command Ok(());
+                }
+
+                for (i, (title, tasks)) in sections.into_iter().enumerate() {
+                    if i > 0 {
+                        println!();
+                    }
+                    if *markdown {
+                        println!("**{title}**");
+                    } else {
+                        println!("{title}:");
+                    }
+
+                    let bullet = if *markdown { "-" } else { "  -" };
+                    if tasks.is_empty() {
+                        println!("{bullet} Nothing");
+                    }
+                    for (id, task) in tasks {
+                        println!("{bullet} {} (#{id})", task.description);
+                    }
+                }
+            }
+            Commands::HandleUri { uri, desktop_entry } => {
+                if *desktop_entry {
+                    install_desktop_entry(output)?;
+                    break 'command Ok(());
+                }
+
+                let uri = uri.as_deref().expect("clap requires a URI");
+                let uri = uri::parse(uri)?;
+
+                match uri.action.as_str() {
+                    "add" => {
+                        let description = uri
+                            .param("description")
+                            .expect("parse requires a description")
+                            .trim();
+                        if description.is_empty() {
+                            return Err(TaskError::Invalid(
+                                "Description is empty, nothing to add".to_string(),
+                            ));
+                        }
+                        let due = uri.param("due").map(parse::parse_due).transpose();
+                        let due = due.map_err(TaskError::Invalid)?;
+                        for (key, _) in &uri.params {
+                            if !["description", "due"].contains(&key.as_str()) {
+                                eprintln!("Warning: ignoring unsupported parameter '{key}'");
+                            }
+                        }
+
+                        warn_unknown_references(&store, description);
+                        let mut task = Task::new(description.to_string(), time);
+                        task.due = due;
+                        let rules = apply_rules(&mut task, output)?;
+                        let new_id = store.insert(task);
+                        save_store(&mut store)?;
+                        log_rules(&rule_log, &store, new_id, rules, time);
+                        note(output, &format!("Created task {new_id}: {description}"));
+                    }
+                    other => unreachable!("parse rejects the unsupported action {other}"),
+                }
+            }
+            Commands::Generate { tasks, seed } => {
+                let seed = seed.unwrap_or_else(|| time.unix_timestamp_nanos() as u64);
+                generate::populate(&mut store, *tasks, seed, time);
+
+                save_store(&mut store)?;
+                note(output, &format!("Generated {tasks} tasks with seed {seed}"));
+            }
+            Commands::Export {
+                format,
+                status,
+                file,
+            } => {
+                let mut tasks = store
+                    .list(status.as_ref())
+                    .into_iter()
+                    .filter(|(_, task)| !task.is_temporary())
+                    .collect::<Vec<_>>();
+                query::sort_tasks(&mut tasks, Sort::Id, false);
+                let exported = match format {
+                    ExportFormat::Csv => export::csv(&tasks),
+                    ExportFormat::Markdown => export::markdown(&tasks),
+                    ExportFormat::Json => export::json(&tasks),
+                    ExportFormat::Todotxt => todotxt::format(&tasks),
+                    ExportFormat::Taskwarrior => taskwarrior::format(&tasks),
+                };
+
+                match file {
+                    Some(file) => {
+                        persist::write_atomic(file, exported.as_bytes())
+                            .map_err(|e| TaskError::Io(file.clone(), e))?;
+                        println!("Exported {} tasks to {}", tasks.len(), file.display());
+                    }
+                    None => print!("{exported}"),
+                }
+            }
+            Commands::Import { format, file } => {
+                let text = fs::read_to_string(file).map_err(|e| TaskError::Io(file.clone(), e))?;
+                let mut skipped = 0;
+                let tasks = match format {
+                    ImportFormat::Todotxt => {
+                        let imported = todotxt::parse(&text, time);
+                        let count = imported.len();
+                        let tasks = imported
+                            .into_iter()
+                            .filter(|imported| !imported_before(&store, imported))
+                            .map(|imported| (imported.task, Vec::new()))
+                            .collect::<Vec<_>>();
+                        skipped = count - tasks.len();
+                        tasks
+                    }
+                    ImportFormat::Taskwarrior => taskwarrior::parse(&text, time)
+                        .map_err(|e| TaskError::Invalid(format!("{}: {e}", file.display())))?
+                        .into_iter()
+                        .map(|imported| (imported.task, imported.depends))
+                        .collect::<Vec<_>>(),
+                };
+
+                // Tasks imported before are skipped, so a migration can be repeated
+                let mut ids_by_uuid = store
+                    .tasks()
+                    .iter()
+                    .filter_map(|(id, task)| Some((task.uuid.clone()?, *id)))
+                    .collect::<HashMap<_, _>>();
+                let mut ids = Vec::new();
+                let mut dependencies = Vec::new();
+                for (task, depends) in tasks {
+                    if let Some(uuid) = &task.uuid
+                        && ids_by_uuid.contains_key(uuid)
+                    {
+                        skipped += 1;
+                        continue;
+                    }
+                    let uuid = task.uuid.clone();
+                    let id = store.insert(task);
+                    if let Some(uuid) = uuid {
+                        ids_by_uuid.insert(uuid, id);
+                    }
+                    ids.push(id);
+                    dependencies.push((id, depends));
+                }
+                for (id, depends) in dependencies {
+                    // Dependencies are part of the imported state, not a change made now
+                    let updated = store.get(id).expect("Task was just imported").updated;
+                    for uuid in depends {
+                        match ids_by_uuid.get(&uuid) {
+                            Some(on) => {
+                                store.add_dependency(id, *on, updated)?;
+                            }
+                            None => eprintln!(
+                                "Warning: task {id} depends on {uuid}, which was not imported"
+                            ),
+                        }
+                    }
+                }
+
+                if skipped > 0 {
+                    note(
+                        output,
+                        &format!("Skipped {skipped} tasks that were imported before"),
+                    );
+                }
+                if ids.is_empty() {
+                    note(output, &format!("No new tasks in {}", file.display()));
+                    break 'command Ok(());
+                }
+                save_store(&mut store)?;
+                note(
+                    output,
+                    &format!("Imported {} tasks as {}", ids.len(), format_ids(&ids)),
+                );
+            }
+            Commands::Diff { snapshot, since } => {
+                let old = match (snapshot, since) {
+                    (Some(snapshot), _) => TaskStore::load(snapshot)?,
+                    (None, Some(since)) => store.as_of(*since)?,
+                    (None, None) => unreachable!("clap requires a snapshot or --since"),
+                };
+
+                let diff = diff::diff(&old, &store, format_time);
+
+                if output == Output::Json {
+                    print_json(&diff);
+                } else {
+                    print_diff(&diff);
+                }
+            }
+            Commands::Rescue => unreachable!("rescue runs before the store is loaded"),
+            Commands::Config { .. } => unreachable!("config runs before the store is loaded"),
+            Commands::Context { .. } => unreachable!("context runs before the store is loaded"),
+            Commands::History {
+                commands: _,
+                rules: true,
+                limit,
+            } => {
+                let entries = history::read::<history::RuleEntry>(&rule_log)
+                    .map_err(|e| TaskError::Io(rule_log.clone(), e))?;
+                if entries.is_empty() && output == Output::Text {
+                    println!("No rules applied yet");
+                    break 'command Ok(());
+                }
+
+                let recent = &entries[entries.len().saturating_sub(*limit)..];
+                match output {
+                    Output::Text => {
+                        for entry in recent {
+                            println!(
+                                "{}  {} (#{}): {}",
+                                format_time(entry.time),
+                                entry.description,
+                                entry.id,
+                                entry.rules.join(", ")
+                            );
+                        }
+                    }
+                    Output::Json => print_json(recent),
+                    Output::Plain => {
+                        for entry in recent {
+                            let time = entry.time.format(&Rfc3339).expect("Failed to format time");
+                            println!("{time}\t{}\t{}", entry.id, entry.rules.join(","));
+                        }
+                    }
+                }
+            }
+            Commands::History { limit, .. } => {
+                let entries = history::read_commands(&history_file)
+                    .map_err(|e| TaskError::Io(history_file.clone(), e))?;
+                if entries.is_empty() && output == Output::Text {
+                    println!("No commands recorded");
+                    if !history::enabled() {
+                        println!("Set {}=1 to record commands", history::HISTORY_ENV);
+                    }
+                    break 'command Ok(());
+                }
+
+                let recent = &entries[entries.len().saturating_sub(*limit)..];
+                match output {
+                    Output::Text => {
+                        for entry in recent {
+                            println!(
+                                "{}  {APPNAME} {}",
+                                format_time(entry.time),
+                                format_command(&entry.args)
+                            );
+                        }
+                    }
+                    Output::Json => print_json(recent),
+                    Output::Plain => {
+                        for entry in recent {
+                            let time = entry.time.format(&Rfc3339).expect("Failed to format time");
+                            println!("{time}\tcommand\t{}", format_command(&entry.args));
+                        }
+                    }
+                }
+            }
+            Commands::Again => {
+                let entries = history::read_commands(&history_file)
+                    .map_err(|e| TaskError::Io(history_file.clone(), e))?;
+                // Entries from older versions may no longer parse, those are passed over
+                let last = entries.iter().rev().find(|entry| {
+                    let args = iter::once(APPNAME).chain(entry.args.iter().map(String::as_str));
+                    Cli::try_parse_from(args)
+                        .is_ok_and(|cli| cli.command.is_some_and(|cmd| cmd.is_mutating()))
+                });
+                let Some(last) = last else {
+                    note(output, "No command to repeat");
+                    if !history::enabled() {
+                        note(
+                            output,
+                            &format!("Set {}=1 to record commands", history::HISTORY_ENV),
+                        );
+                    }
+                    break 'command Ok(());
+                };
+
+                note(
+                    output,
+                    &format!("Repeating: {APPNAME} {}", format_command(&last.args)),
+                );
+                // The repeated command takes the lock itself
+                drop(lock);
+                let exe = env::current_exe().map_err(|e| {
+                    TaskError::Other(format!("Failed to locate the {APPNAME} executable: {e}"))
+                })?;
+                let mut command = process::Command::new(&exe);
+                // The command was recorded with its own output format unless it had none
+                if output != Output::Text
+                    && !last.args.iter().any(|arg| arg.starts_with("--output"))
+                {
+                    let format = output
+                        .to_possible_value()
+                        .expect("Output has no skipped values");
+                    command.args(["--output", format.get_name()]);
+                }
+                let status = command
+                    .args(&last.args)
+                    .status()
+                    .map_err(|e| TaskError::Io(exe, e))?;
+                // The repeated command already reported its own errors
+                process::exit(status.code().unwrap_or(EXIT_FAILURE));
+            }
+            Commands::Undo { count } => {
+                let undone = store.undo(*count, time)?;
+                if undone.is_empty() {
+                    note(output, "Nothing to undo");
+                    break 'command Ok(());
+                }
+                save_replayed(&mut store, &archive_filepath)?;
+                for label in undone {
+                    note(output, &format!("Undid: {APPNAME} {label}"));
+                }
+            }
+            Commands::Redo { count } => {
+                let redone = store.redo(*count, time)?;
+                if redone.is_empty() {
+                    note(output, "Nothing to redo");
+                    break 'command Ok(());
+                }
+                save_replayed(&mut store, &archive_filepath)?;
+                for label in redone {
+                    note(output, &format!("Redid: {APPNAME} {label}"));
+                }
+            }
+        }
+        Ok(())
+    };
+
+    // Tasks skipped by a bulk command fail it only after the others are saved
+    if let Some(before) = before {
+        print_json(&changes(&before, store.tasks()));
+    }
+    result?;
+    if cmd.is_mutating() {
+        warn_limits(&store, &config);
+    }
+
+    Ok(())
+}
+
+fn print_diff(diff: &StoreDiff) {
+    if diff.added.is_empty() && diff.removed.is_empty() && diff.changed.is_empty() {
+        println!("No changes");
+        return;
+    }
+
+    if !diff.added.is_empty() {
+        println!("Added:");
+        for DiffTask { id, task } in &diff.added {
+            println!("  + {id}: {} [{}]", task.description, task.status);
+        }
+        println!();
+    }
+    if !diff.removed.is_empty() {
+        println!("Removed:");
+        for DiffTask { id, task } in &diff.removed {
+            println!("  - {id}: {} [{}]", task.description, task.status);
+        }
+        println!();
+    }
+    if !diff.changed.is_empty() {
+        println!("Changed:");
+        for ChangedTask { id, fields } in &diff.changed {
+            println!("  ~ {id}:");
+            for FieldChange { field, old, new } in fields {
+                println!("      {field}: {old:?} -> {new:?}");
+            }
+        }
+        println!();
+    }
+}
+
+fn install_desktop_entry(output: Output) -> Result<(), TaskError> {
+    let exe = env::current_exe()
+        .map_err(|e| TaskError::Other(format!("Failed to locate the {APPNAME} executable: {e}")))?;
+    let applications = match utils::get_dir_with(Dirs::Data, "applications") {
+        Ok(p) => p,
+        Err(DirError::DoesNotExist(path)) => {
+            fs::create_dir_all(&path).map_err(|e| TaskError::Io(path.clone(), e))?;
+            path
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let entry_path = applications.join(format!("{APPNAME}-uri.desktop"));
+    fs::write(&entry_path, uri::desktop_entry(&exe))
+        .map_err(|e| TaskError::Io(entry_path.clone(), e))?;
+
+    note(
+        output,
+        &format!("Installed desktop entry at {entry_path:?}"),
+    );
+    note(output, "Register it as the default handler with:");
+    note(
+        output,
+        &format!(
+            "  xdg-mime default {APPNAME}-uri.desktop x-scheme-handler/{}",
+            uri::SCHEME
+        ),
+    );
+    Ok(())
+}
+
+fn rescue(path: &Path) -> Result<(), TaskError> {
+    if !path.exists() {
+        println!("No tasks found, there is nothing to rescue");
+        return Ok(());
+    }
+
+    let mut session = match Rescue::open(path) {
+        Ok(session) => session,
+        Err(e) if persist::backup_path(path).exists() && io::stdin().is_terminal() => {
+            eprintln!("{e}");
+            if prompt("Start from the backup instead? [y/N] ") != "y" {
+                return Ok(());
+            }
+            Rescue::from_backup(path).map_err(TaskError::Other)?
+        }
+        Err(e) => return Err(TaskError::Other(e)),
+    };
+    session
+        .run()
+        .map_err(|e| TaskError::Io("<stdin>".into(), e))
+}
+
+/// Asks for a description on the terminal, `None` when cancelled or left empty
+fn read_quick_entry() -> Result<Option<String>, TaskError> {
+    if !io::stdin().is_terminal() {
+        return Err(TaskError::Invalid(format!(
+            "quick needs a terminal, use '{APPNAME} add' in scripts"
+        )));
+    }
+    let line = quick::read_line("Add task: ").map_err(|e| TaskError::Io("<stdin>".into(), e))?;
+    Ok(line
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty()))
+}
+
+/// The archive is a store of its own, kept out of the journal so undo only covers the task list
+fn open_archive(path: &Path) -> Result<TaskStore, TaskError> {
+    let mut archive = TaskStore::open(path)?;
+    archive.disable_journal();
+    Ok(archive)
+}
+
+/// Whether a task like the todo.txt line is already in the store. Lines have no IDs, so a task
+/// with the same description and project counts as the same one, created on the same day when
+/// the line says when.
+fn imported_before(store: &TaskStore, imported: &todotxt::Imported) -> bool {
+    store.tasks().values().any(|task| {
+        task.description == imported.task.description
+            && task.project == imported.task.project
+            && imported
+                .created
+                .is_none_or(|created| task.created.date() == created)
+    })
+}
+
+/// Saves an undo or redo together with the archive when it moved archived tasks. The file that
+/// gains tasks is saved first, so a failure leaves them in both rather than in neither.
+fn save_replayed(store: &mut TaskStore, archive_path: &Path) -> Result<(), TaskError> {
+    let moves = store.take_archive_moves();
+    if moves.archive.is_empty() && moves.unarchive.is_empty() {
+        return save_store(store);
+    }
+
+    let mut archive = open_archive(archive_path)?;
+    let archive_first = moves.unarchive.is_empty();
+    for (id, task) in moves.archive {
+        archive.restore(id, task);
+    }
+    for id in moves.unarchive {
+        // Already gone if the archive was edited by hand
+        let _ = archive.remove(id);
+    }
+    if archive_first {
+        save_store(&mut archive)?;
+        save_store(store)
+    } else {
+        save_store(store)?;
+        save_store(&mut archive)
+    }
+}
+
+/// Nudges towards cleaning up once the task list grows past the soft limits
+fn warn_limits(store: &TaskStore, config: &Config) {
+    let (limits, problems) = Limits::load(config);
+    let open = store
+        .tasks()
+        .values()
+        .filter(|task| !matches!(task.status, TaskStatus::Complete | TaskStatus::Someday))
+        .count();
+    let size = fs::metadata(store.path()).map_or(0, |metadata| metadata.len());
+    for warning in problems.into_iter().chain(limits.check(open as u64, size)) {
+        eprintln!("Warning: {warning}");
+    }
+}
+
+fn lock_store(path: &Path) -> Result<persist::Lock, TaskError> {
+    let lock = persist::try_lock(path).and_then(|lock| match lock {
+        Some(lock) => Ok(lock),
+        None => {
+            if io::stderr().is_terminal() {
+                eprintln!("Waiting for another taskly process to finish...");
+            }
+            persist::lock(path)
+        }
+    });
+    lock.map_err(|e| TaskError::Io(persist::lock_path(path), e))
+}
+
+/// Writes the store to disk. When the write fails the user is offered an alternative location
+/// so the in-memory changes are not lost.
+fn save_store(store: &mut TaskStore) -> Result<(), TaskError> {
+    save_store_with(store, TaskStore::save)
+}
+
+fn save_store_with(
+    store: &mut TaskStore,
+    save: fn(&mut TaskStore) -> Result<(), StoreError>,
+) -> Result<(), TaskError> {
+    match save(store) {
+        Ok(()) => Ok(()),
+        // Saved, but without a checksum the next load cannot tell a damaged file apart
+        Err(StoreError::Io(path, e)) if path == integrity::sidecar_path(store.path()) => {
+            eprintln!("The tasks were saved, but not their checksum");
+            Err(TaskError::Io(path, e))
+        }
+        Err(StoreError::Io(path, e)) => {
+            report_write_error(&path, &e);
+            // Only the journal failing still leaves the tasks saved
+            if path == journal::journal_path(store.path()) {
+                return Ok(());
+            }
+            offer_alternative_path(&store.to_json());
+            Err(TaskError::Unsaved)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn report_write_error(path: &Path, e: &io::Error) {
+    match e.kind() {
+        io::ErrorKind::StorageFull | io::ErrorKind::QuotaExceeded => {
+            eprintln!("No space left to write {path:?}, free up some disk space and try again")
+        }
+        io::ErrorKind::ReadOnlyFilesystem => {
+            eprintln!("Cannot write {path:?} because it is on a read-only filesystem")
+        }
+        io::ErrorKind::PermissionDenied => {
+            eprintln!("Permission denied while writing {path:?}, check the file's permissions")
+        }
+        _ => eprintln!("Failed to write {path:?}: {e}"),
+    }
+}
+
+/// The first non-empty line of piped or file input is the description, the rest becomes a note
+fn split_description(input: &str) -> (String, Option<String>) {
+    let mut lines = input.lines().skip_while(|line| line.trim().is_empty());
+    let description = lines.next().unwrap_or_default().trim().to_string();
+
+    let rest = lines.collect::<Vec<_>>().join("\n");
+    let note = Some(rest.trim().to_string()).filter(|rest| !rest.is_empty());
+    (description, note)
+}
+
+/// Asks a question on stdin and returns the trimmed, lowercased answer
+fn prompt(question: &str) -> String {
+    ask(question).to_lowercase()
+}
+
+/// Asks a question on stdin and returns the trimmed answer
+fn ask(question: &str) -> String {
+    print!("{question}");
+    io::stdout().flush().expect("Failed to flush stdout");
+
+    let mut input = String::new();
+    io::stdin()
+        .read_line(&mut input)
+        .expect("Failed to read from stdin");
+    input.trim().to_string()
+}
+
+/// Reads the template and fills in its placeholders from `--var`, asking for the rest
+fn fill_template(name: &str, vars: &[(String, String)]) -> Result<String, TaskError> {
+    let dir = utils::get_app_dir(Dirs::Config).or_else(|e| match e {
+        DirError::DoesNotExist(dir) => Ok(dir),
+        e => Err(e),
+    })?;
+    let text = match template::read(&dir, name) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(TaskError::Invalid(format!(
+                "No template named '{name}', templates are read from {:?}",
+                template::template_path(&dir, name)
+            )));
+        }
+        Err(e) => return Err(TaskError::Io(template::template_path(&dir, name), e)),
+    };
+
+    let placeholders = template::placeholders(&text);
+    for (var, _) in vars {
+        if !placeholders.contains(&var.as_str()) {
+            eprintln!("Warning: template '{name}' has no placeholder {{{var}?}}");
+        }
+    }
+
+    let mut values = vars.iter().cloned().collect::<HashMap<_, _>>();
+    for placeholder in placeholders {
+        if values.contains_key(placeholder) {
+            continue;
+        }
+        if !io::stdin().is_terminal() {
+            return Err(TaskError::Invalid(format!(
+                "Template '{name}' needs a value for {placeholder}, pass --var {placeholder}=..."
+            )));
+        }
+        values.insert(placeholder.to_string(), ask(&format!("{placeholder}: ")));
+    }
+    Ok(template::expand(&text, &values))
+}
+
+fn offer_alternative_path(json: &str) {
+    if !io::stdin().is_terminal() {
+        return;
+    }
+
+    loop {
+        print!("Save your tasks to another path instead? (leave empty to discard): ");
+        io::stdout().flush().expect("Failed to flush stdout");
+
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input).is_err() {
+            return;
+        }
+        let input = input.trim();
+        if input.is_empty() {
+            return;
+        }
+
+        let path = Path::new(input);
+        match fs::write(path, json) {
+            Ok(()) => {
+                println!(
+                    "Saved tasks to {path:?}, copy it over tasks.json once the problem is fixed"
+                );
+                return;
+            }
+            Err(e) => report_write_error(path, &e),
+        }
+    }
+}
+
+/// Deletes the temporary tasks `matching` accepts and saves, returning their IDs. Those with
+/// subtasks of their own are kept. Sweeps run before every command, they stay quiet unless
+/// something was deleted.
+fn clear_temporary(
+    store: &mut TaskStore,
+    matching: impl Fn(&Task) -> bool,
+    sweep: bool,
+) -> Result<Vec<u64>, TaskError> {
+    let mut ids = store
+        .list(None)
+        .into_iter()
+        .filter(|(_, task)| task.is_temporary() && matching(task))
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+    ids.sort_unstable();
+
+    let mut cleared = Vec::new();
+    for id in ids {
+        match store.delete(id) {
+            Ok(_) => cleared.push(id),
+            Err(StoreError::HasSubtasks(..)) if !sweep => {
+                eprintln!("Kept temporary task {id}, it has subtasks");
+            }
+            Err(StoreError::HasSubtasks(..)) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    if !cleared.is_empty() {
+        if sweep {
+            // Expiring is not something the user did, undo should not revert it
+            save_store_with(store, TaskStore::save_unjournaled)?;
+            eprintln!("Deleted expired temporary tasks {}", format_ids(&cleared));
+        } else {
+            save_store(store)?;
+        }
+    }
+    Ok(cleared)
+}
+
+/// A copy of the task if its description or tags match, with the first match in each
+/// highlighted when `highlight` is set
+fn highlight_match(task: &Task, pattern: &Pattern, highlight: bool) -> Option<Task> {
+    let found = query::find(task, pattern)?;
+    let Some(theme) = THEME.get().filter(|_| highlight) else {
+        return Some(task.clone());
+    };
+    let paint = |text: &mut String, range: Option<Range<usize>>| {
+        if let Some(range) = range {
+            *text = format!(
+                "{}{}{}",
+                &text[..range.start],
+                theme.matched.paint(&text[range.clone()]),
+                &text[range.end..]
+            );
+        }
+    };
+
+    let mut task = task.clone();
+    paint(&mut task.description, found.description);
+    for (tag, range) in task.tags.iter_mut().zip(found.tags) {
+        paint(tag, range);
+    }
+    Some(task)
+}
+
+fn configure(path: &Path, command: &ConfigCommand, output: Output) -> Result<(), TaskError> {
+    match command {
+        ConfigCommand::Get { key } => {
+            let value = utils::get_config_value(path, key)?;
+            if output == Output::Json {
+                print_json(&value);
+            } else if let Some(value) = value {
+                println!("{value}");
+            }
+        }
+        ConfigCommand::Set { key, value } => {
+            utils::set_config_value(path, key, value)?;
+            note(output, &format!("Set {key} in {}", path.display()));
+            if output == Output::Json {
+                print_json(&utils::get_config_value(path, key)?);
+            }
+        }
+        ConfigCommand::Path if output == Output::Json => print_json(path),
+        ConfigCommand::Path => println!("{}", path.display()),
+    }
+    Ok(())
+}
+
+fn switch_context(
+    data_dir: &Path,
+    command: &ContextCommand,
+    output: Output,
+) -> Result<(), TaskError> {
+    let io_error = |e| TaskError::Io(data_dir.to_path_buf(), e);
+    match command {
+        ContextCommand::List => {
+            let active = profile::active(data_dir);
+            let names = profile::list(data_dir).map_err(io_error)?;
+            if output == Output::Json {
+                let profiles = names
+                    .iter()
+                    .map(|name| Profile {
+                        active: *name == active,
+                        name,
+                    })
+                    .collect::<Vec<_>>();
+                print_json(&profiles);
+                return Ok(());
+            }
+            for name in names {
+                if name == active {
+                    println!("* {name}");
+                } else {
+                    println!("  {name}");
+                }
+            }
+        }
+        ContextCommand::Switch { name } => {
+            let dir = profile::dir(data_dir, name);
+            let created = !dir.exists();
+            fs::create_dir_all(&dir).map_err(|e| TaskError::Io(dir.clone(), e))?;
+            profile::set_active(data_dir, name).map_err(io_error)?;
+            if created {
+                note(
+                    output,
+                    &format!("Created profile {name} and switched to it"),
+                );
+            } else {
+                note(output, &format!("Switched to profile {name}"));
+            }
+            if output == Output::Json {
+                print_json(&Profile { name, active: true });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prints the tasks as a table, or every field of each with `long`. Subtasks follow their parent
+/// when both are listed.
+fn list_tasks(
+    tasks: &[(u64, &Task)],
+    store: &TaskStore,
+    now: OffsetDateTime,
+    long: bool,
+    output: Output,
+) {
+    let ordered = export::tree(tasks);
+
+    match output {
+        Output::Text => {}
+        Output::Json => {
+            let tasks = ordered
+                .into_iter()
+                .map(|(id, task, _)| JsonTask { id, task })
+                .collect::<Vec<_>>();
+            print_json(&tasks);
+            return;
+        }
+        Output::Plain => {
+            for (id, task, _) in ordered {
+                print_plain(id, task);
+            }
+            return;
+        }
+    }
+    if long {
+        for (id, task, depth) in ordered {
+            print_task(id, task, store, now, &"    ".repeat(depth));
+        }
+        return;
+    }
+    if ordered.is_empty() {
+        return;
+    }
+
+    let date_format =
+        format_description::parse("[year]-[month]-[day]").expect("Failed parse format");
+    let mut table = Table::new(vec!["ID", "Status", "Description", "Age", "Due"]).flex(2);
+    for (id, task, depth) in ordered {
+        let due = task.due.map_or_else(String::new, |due| {
+            let date = due.format(&date_format).expect("Failed to format date");
+            if let Some(days) = rolled_over(task, now) {
+                format!("{date} rolled over {days}x")
+            } else if task.is_overdue(now) {
+                paint(format!("{date} overdue"), |theme| Some(&theme.overdue))
+            } else {
+                date
+            }
+        });
+        table.push(vec![
+            id.to_string(),
+            paint(task.status.to_string(), |theme| {
+                Some(theme.status(&task.status))
+            }),
+            format!(
+                "{}{}",
+                "  ".repeat(depth),
+                paint(task.description.clone(), |theme| theme
+                    .priority(task.priority))
+            ),
+            format_age(now - task.created),
+            due,
+        ]);
+    }
+    print!("{}", table.render(table::terminal_width()));
+}
+
+#[derive(Serialize)]
+struct ProjectCount<'a> {
+    name: &'a str,
+    open: usize,
+    total: usize,
+}
+
+#[derive(Serialize)]
+struct Profile<'a> {
+    name: &'a str,
+    active: bool,
+}
+
+#[derive(Serialize)]
+struct Standup<'a> {
+    yesterday: Vec<JsonTask<'a>>,
+    today: Vec<JsonTask<'a>>,
+    blockers: Vec<JsonTask<'a>>,
+}
+
+/// IDs of the tasks a command added, changed or removed, the JSON output of commands that have
+/// none of their own
+#[derive(Serialize)]
+struct Changes {
+    added: Vec<u64>,
+    changed: Vec<u64>,
+    removed: Vec<u64>,
+}
+
+fn changes(before: &HashMap<u64, Task>, after: &HashMap<u64, Task>) -> Changes {
+    let mut changes = Changes {
+        added: Vec::new(),
+        changed: Vec::new(),
+        removed: Vec::new(),
+    };
+    for (id, task) in after {
+        match before.get(id) {
+            None => changes.added.push(*id),
+            Some(old) if old != task => changes.changed.push(*id),
+            Some(_) => {}
+        }
+    }
+    changes.removed = before
+        .keys()
+        .filter(|id| !after.contains_key(id))
+        .copied()
+        .collect();
+    for ids in [
+        &mut changes.added,
+        &mut changes.changed,
+        &mut changes.removed,
+    ] {
+        ids.sort_unstable();
+    }
+    changes
+}
+
+fn print_json<T: Serialize + ?Sized>(value: &T) {
+    let json = serde_json::to_string_pretty(value).expect("Failed to serialize output");
+    println!("{json}");
+}
+
+/// ID, status, description, due date, tags and project separated by tabs. Tabs and newlines in
+/// the description are replaced by spaces so every task stays on one line.
+fn print_plain(id: u64, task: &Task) {
+    let due = task.due.map_or_else(String::new, |due| {
+        due.format(&Rfc3339).expect("Failed to format due date")
+    });
+    println!(
+        "{id}\t{}\t{}\t{due}\t{}\t{}",
+        task.status,
+        task.description.replace(['\t', '\n'], " "),
+        task.tags.join(","),
+        task.project.as_deref().unwrap_or_default()
+    );
+}
+
+/// Reports a created task for scripts, nothing is printed in text mode
+fn print_created(store: &TaskStore, id: u64, output: Output) {
+    let task = store.get(id).expect("Task was just created");
+    match output {
+        Output::Text => {}
+        Output::Json => print_json(&JsonTask { id, task }),
+        Output::Plain => print_plain(id, task),
+    }
+}
+
+/// Days the task rolled over when rollover is on in the config
+fn rolled_over(task: &Task, now: OffsetDateTime) -> Option<i64> {
+    ROLLOVER
+        .get()
+        .is_some_and(|on| *on)
+        .then(|| task.days_rolled_over(now))
+        .flatten()
+}
+
+/// `text` in the style the theme picks, unchanged when output is not colored or the theme
+/// has no style for it
+fn paint(text: String, style: impl FnOnce(&'static Theme) -> Option<&'static Style>) -> String {
+    match THEME.get().and_then(style) {
+        Some(style) => style.paint(&text),
+        None => text,
+    }
+}
+
+/// Compact age such as 5m, 3h, 2d, 6w or 4y
+fn format_age(age: Duration) -> String {
+    match age {
+        age if age < Duration::HOUR => format!("{}m", age.whole_minutes().max(0)),
+        age if age < Duration::DAY => format!("{}h", age.whole_hours()),
+        age if age < Duration::weeks(2) => format!("{}d", age.whole_days()),
+        age if age < Duration::days(365) => format!("{}w", age.whole_weeks()),
+        age => format!("{}y", age.whole_days() / 365),
+    }
+}
+
+fn print_task(id: u64, task: &Task, store: &TaskStore, now: OffsetDateTime, indent: &str) {
+    println!("{indent}Id: {}", id);
+    println!("{indent}Description: {}", task.description);
+    let status = paint(task.status.to_string(), |theme| {
+        Some(theme.status(&task.status))
+    });
+    println!("{indent}Status: {status}");
+    if let Some(priority) = task.priority {
+        let priority = paint(priority.to_string(), |theme| theme.priority(Some(priority)));
+        println!("{indent}Priority: {priority}");
+    }
+    if let Some(project) = &task.project {
+        println!("{indent}Project: {project}");
+    }
+    if !task.tags.is_empty() {
+        println!("{indent}Tags: {}", task.tags.join(", "));
+    }
+    if let Some(parent) = task.parent {
+        println!("{indent}Parent: #{parent}");
+    }
+    let subtasks = store.children(id);
+    if !subtasks.is_empty() {
+        println!("{indent}Subtasks: {}", format_ids(&subtasks));
+    }
+    if let Some(repeat) = task.repeat {
+        println!("{indent}Repeats: {repeat}");
+    }
+    if let Some(session) = &task.session {
+        println!("{indent}Session: {session}");
+    }
+    if !task.waiting_on.is_empty() {
+        let conditions = task.waiting_on.iter().map(Condition::to_string);
+        println!(
+            "{indent}Waiting on: {}",
+            conditions.collect::<Vec<_>>().join(", ")
+        );
+    }
+    let blockers = store.blockers(id);
+    if !blockers.is_empty() {
+        println!("{indent}Blocked by: {}", format_ids(&blockers));
+    }
+    println!("{indent}Created: {}", format_time(task.created));
+    println!("{indent}Updated: {}", format_time(task.updated));
+    if let Some(reviewed) = task.reviewed {
+        println!("{indent}Reviewed: {}", format_time(reviewed));
+    }
+    if let Some(expires) = task.expires {
+        println!("{indent}Expires: {}", format_time(expires));
+    }
+    if let Some(due) = task.due {
+        if let Some(days) = rolled_over(task, now) {
+            let unit = if days == 1 { "day" } else { "days" };
+            println!(
+                "{indent}Due: {} (rolled over {days} {unit})",
+                format_time(due)
+            );
+        } else if task.is_overdue(now) {
+            let overdue = paint("(OVERDUE)".to_string(), |theme| Some(&theme.overdue));
+            println!("{indent}Due: {} {overdue}", format_time(due));
+        } else {
+            println!("{indent}Due: {}", format_time(due));
+        }
+    }
+    if let Some(remind) = task.remind {
+        println!("{indent}Remind: {} before it is due", format_age(remind));
+    }
+    if let Some(uuid) = &task.uuid {
+        println!("{indent}Taskwarrior UUID: {uuid}");
+    }
+
+    let referrers = store.backlinks(id);
+    if !referrers.is_empty() {
+        println!(
+            "{indent}Referenced by: {} {} ({})",
+            referrers.len(),
+            if referrers.len() == 1 {
+                "task"
+            } else {
+                "tasks"
+            },
+            format_ids(&referrers)
+        );
+    }
+    if !task.notes.is_empty() {
+        println!("{indent}Notes:");
+        for note in &task.notes {
+            let time = format_time(note.time);
+            // Lines after the first stay aligned with the text rather than the time
+            let continued = " ".repeat(time.len());
+            for (i, line) in note.text.lines().enumerate() {
+                let prefix = if i == 0 { &time } else { &continued };
+                println!("{indent}  {prefix}  {line}");
+            }
+        }
+    }
+    println!();
+}
+
+/// Applies the auto-tagging rules in the config dir, returning the names of those that matched
+fn apply_rules(task: &mut Task, output: Output) -> Result<Vec<String>, TaskError> {
+    let path = match utils::get_app_dir(Dirs::Config) {
+        Ok(dir) => dir.join(rules::RULES_FILE),
+        Err(DirError::DoesNotExist(_)) => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let rules = rules::load(&path)?;
+
+    let applied = rules::apply(&rules, task);
+    if !applied.is_empty() {
+        note(output, &format!("Applied rules: {}", applied.join(", ")));
+    }
+    Ok(applied.into_iter().map(String::from).collect())
+}
+
+/// Records the rules applied to a task once it is saved
+fn log_rules(path: &Path, store: &TaskStore, id: u64, rules: Vec<String>, now: OffsetDateTime) {
+    let Some(task) = store.get(id).filter(|_| !rules.is_empty()) else {
+        return;
+    };
+    let entry = history::RuleEntry {
+        time: now,
+        id,
+        description: task.description.clone(),
+        rules,
+    };
+    if let Err(e) = history::record_rules(path, &entry) {
+        eprintln!("Failed to record the applied rules: {e}");
+    }
+}
+
+/// The per-tag and per-project defaults in the config dir, none if it does not exist
+fn load_defaults() -> Result<Defaults, TaskError> {
+    match utils::get_app_dir(Dirs::Config) {
+        Ok(dir) => Ok(defaults::load(&dir.join(defaults::DEFAULTS_FILE))?),
+        Err(DirError::DoesNotExist(_)) => Ok(Defaults::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Fills in the defaults of the tags and project of a task about to be added, saying where
+/// they came from
+fn apply_defaults(defaults: &Defaults, task: &mut Task, now: OffsetDateTime, output: Output) {
+    let (tags, project) = (task.tags.clone(), task.project.clone());
+    let applied = defaults.apply(task, &tags, project.as_deref(), now);
+    if !applied.is_empty() {
+        note(
+            output,
+            &format!("Applied defaults of {}", applied.join(", ")),
+        );
+    }
+}
+
+/// Informational output, kept off stdout when that is meant for scripts
+fn note(output: Output, message: &str) {
+    match output {
+        Output::Text => println!("{message}"),
+        Output::Json | Output::Plain => eprintln!("{message}"),
+    }
+}
+
+/// `apply_defaults` for a task that is already in the store
+fn apply_stored_defaults(
+    store: &mut TaskStore,
+    defaults: &Defaults,
+    id: u64,
+    tags: &[String],
+    project: Option<&str>,
+    now: OffsetDateTime,
+    output: Output,
+) -> Result<(), TaskError> {
+    if tags.is_empty() && project.is_none() {
+        return Ok(());
+    }
+    let old = store.get(id).ok_or(StoreError::NotFound(id))?.clone();
+    let mut task = old.clone();
+    let applied = defaults.apply(&mut task, tags, project, now);
+    if !applied.is_empty() {
+        note(
+            output,
+            &format!("Applied defaults of {}", applied.join(", ")),
+        );
+    }
+
+    if let Some(priority) = task.priority
+        && old.priority.is_none()
+    {
+        store.set_priority(id, priority, now)?;
+    }
+    if task.due.is_some() && old.due.is_none() {
+        store.set_due(id, task.due, now)?;
+    }
+    if task.remind.is_some() && old.remind.is_none() {
+        store.set_remind(id, task.remind, now)?;
+    }
+    Ok(())
+}
+
+fn warn_unknown_references(store: &TaskStore, text: &str) {
+    for id in store.unknown_references(text) {
+        eprintln!("Warning: #{id} does not refer to an existing task");
+    }
+}
+
+/// Sets `status` on every task in one save. Missing and blocked tasks are skipped and returned
+/// for `bulk_result` instead of aborting the rest.
+fn set_statuses(
+    store: &mut TaskStore,
+    ids: &BTreeSet<u64>,
+    status: &TaskStatus,
+    force: bool,
+    now: OffsetDateTime,
+    output: Output,
+) -> Result<(Vec<u64>, Option<String>), TaskError> {
+    let completing = *status == TaskStatus::Complete;
+
+    let mut changed = Vec::new();
+    let mut unchanged = Vec::new();
+    let mut missing = Vec::new();
+    let mut blocked = Vec::new();
+    for &id in ids {
+        let Some(task) = store.get(id) else {
+            missing.push(id);
+            continue;
+        };
+        if task.status == *status {
+            unchanged.push(id);
+            continue;
+        }
+
+        let blockers = store.blockers(id);
+        if completing && !blockers.is_empty() {
+            if !force {
+                blocked.push(id);
+                continue;
+            }
+            eprintln!(
+                "Warning: completing task {id} with open dependencies: {}",
+                format_ids(&blockers)
+            );
+        }
+
+        if let Some(next) = store.set_status(id, status.clone(), now)? {
+            print_next_occurrence(store, next, output);
+        }
+        if completing {
+            warn_open_subtasks(store, id);
+        }
+        changed.push(id);
+    }
+
+    if !changed.is_empty() {
+        save_store(store)?;
+        let message = match status {
+            TaskStatus::Complete => format!("Completed {}", format_ids(&changed)),
+            status => format!("Set {} to {status}", format_ids(&changed)),
+        };
+        note(output, &message);
+    }
+    if !unchanged.is_empty() {
+        note(
+            output,
+            &format!("Already {status}: {}", format_ids(&unchanged)),
+        );
+    }
+    let skipped = (!blocked.is_empty()).then(|| {
+        format!(
+            "Skipped {}, which are blocked by open dependencies. Complete those first or pass \
+             --force",
+            format_ids(&blocked)
+        )
+    });
+    Ok((missing, skipped))
+}
+
+/// Shows what is about to be deleted and asks for a yes. There is nobody to ask when stdin is
+/// not a terminal, scripts have to pass --yes instead.
+fn confirm_delete(store: &TaskStore, ids: &[u64]) -> Result<bool, TaskError> {
+    const PREVIEW_LEN: usize = 10;
+
+    if !io::stdin().is_terminal() {
+        return Err(TaskError::Invalid(
+            "Not deleting without confirmation, pass --yes to delete from scripts".to_string(),
+        ));
+    }
+
+    let description = |id: &u64| store.get(*id).map_or("", |task| task.description.as_str());
+    let answer = match ids {
+        [id] => prompt(&format!("Delete task {id} '{}'? [y/N] ", description(id))),
+        ids => {
+            for id in ids.iter().take(PREVIEW_LEN) {
+                println!("  {id}: {}", description(id));
+            }
+            if ids.len() > PREVIEW_LEN {
+                println!("  ... and {} more", ids.len() - PREVIEW_LEN);
+            }
+            prompt(&format!("Delete these {} tasks? [y/N] ", ids.len()))
+        }
+    };
+    Ok(matches!(answer.as_str(), "y" | "yes"))
+}
+
+/// Fails a bulk command after the fact when some of its tasks were missing or skipped
+fn bulk_result(missing: Vec<u64>, skipped: Option<String>) -> Result<(), TaskError> {
+    match (missing.is_empty(), skipped) {
+        (true, None) => Ok(()),
+        (true, Some(skipped)) => Err(TaskError::Conflict(skipped)),
+        (false, skipped) => {
+            if let Some(skipped) = skipped {
+                eprintln!("{skipped}");
+            }
+            Err(TaskError::Missing(missing))
+        }
+    }
+}
+
+fn expand_ids(ranges: &[RangeInclusive<u64>]) -> BTreeSet<u64> {
+    ranges.iter().cloned().flatten().collect()
+}
+
+fn warn_open_subtasks(store: &TaskStore, id: u64) {
+    let open = store
+        .children(id)
+        .into_iter()
+        .filter(|child| {
+            store
+                .get(*child)
+                .is_some_and(|task| task.status != TaskStatus::Complete)
+        })
+        .collect::<Vec<_>>();
+    if !open.is_empty() {
+        eprintln!(
+            "Warning: task {id} still has open subtasks: {}",
+            format_ids(&open)
+        );
+    }
+}
+
+fn print_next_occurrence(store: &TaskStore, id: u64, output: Output) {
+    let due = store
+        .get(id)
+        .and_then(|task| task.due)
+        .expect("Next occurrence has a due date");
+    note(
+        output,
+        &format!("Next occurrence is task {id}, due {}", format_time(due)),
+    );
+}
+
+/// Arguments joined for display, quoting those that would not survive being retyped as is
+fn format_command(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            if arg.is_empty() || arg.contains(char::is_whitespace) {
+                format!("{arg:?}")
+            } else {
+                arg.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn format_ids(ids: &[u64]) -> String {
+    ids.iter()
+        .map(|id| format!("#{id}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn format_time(time: OffsetDateTime) -> String {
+    let format = format_description::parse(DATE_FORMAT.get().map_or(
+        "[year].[month].[day] at [hour]:[minute]:[second] [offset_hour sign:mandatory]:[offset_minute]",
+        String::as_str,
+    ))
+    .expect("Failed parse format");
+
+    time.format(&format).expect("Failed to format time")
+}
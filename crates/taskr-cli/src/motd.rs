@@ -0,0 +1,97 @@
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+use taskr_core::{
+    persist,
+    store::{Task, TaskStore},
+};
+use time::{Duration, OffsetDateTime};
+
+pub const MOTD_FILE: &str = "motd.json";
+/// A new shell within this long of the last summary prints nothing
+pub const THROTTLE: Duration = Duration::hours(1);
+
+#[derive(Deserialize, Serialize)]
+struct Shown {
+    #[serde(with = "time::serde::rfc3339")]
+    time: OffsetDateTime,
+}
+
+/// When the summary was last printed. A missing or unreadable file counts as never.
+pub fn last_shown(path: &Path) -> Option<OffsetDateTime> {
+    let json = fs::read_to_string(path).ok()?;
+    serde_json::from_str::<Shown>(&json)
+        .ok()
+        .map(|shown| shown.time)
+}
+
+pub fn record_shown(path: &Path, now: OffsetDateTime) -> std::io::Result<()> {
+    let json = serde_json::to_string(&Shown { time: now }).expect("Failed to serialize time");
+    persist::write_atomic(path, json.as_bytes())
+}
+
+/// Two lines: what is overdue and due today, and how many tasks changed since `since`
+pub fn summary(
+    store: &TaskStore,
+    since: Option<OffsetDateTime>,
+    end_of_today: OffsetDateTime,
+    now: OffsetDateTime,
+) -> [String; 2] {
+    let open = store
+        .tasks()
+        .values()
+        .filter(|task| task.status.is_open() && !task.is_temporary())
+        .collect::<Vec<_>>();
+    let overdue = open.iter().filter(|task| task.is_overdue(now)).count();
+    let due_today = open
+        .iter()
+        .filter(|task| {
+            task.due
+                .is_some_and(|due| due >= now && due <= end_of_today)
+        })
+        .count();
+    let due = format!(
+        "{} open, {overdue} overdue, {due_today} due today",
+        plural(open.len(), "task")
+    );
+
+    let changes = match since {
+        Some(since) => {
+            let changed = store
+                .tasks()
+                .values()
+                .filter(|task| task.updated > since)
+                .count();
+            match changed {
+                0 => "No changes since the last summary".to_string(),
+                n => format!("{} changed since the last summary", plural(n, "task")),
+            }
+        }
+        None => "First summary, changes are counted from now on".to_string(),
+    };
+    [due, changes]
+}
+
+/// Open tasks whose reminder started but that are not due yet, soonest due first
+pub fn reminders(store: &TaskStore, now: OffsetDateTime) -> Vec<(u64, &Task)> {
+    let mut tasks = store
+        .list(None)
+        .into_iter()
+        .filter(|(_, task)| {
+            task.status.is_open()
+                && !task.is_temporary()
+                && task.reminder().is_some_and(|reminder| reminder <= now)
+                && !task.is_overdue(now)
+        })
+        .collect::<Vec<_>>();
+    tasks.sort_by_key(|(id, task)| (task.due, *id));
+    tasks
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
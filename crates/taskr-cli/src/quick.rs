@@ -0,0 +1,113 @@
+use std::{
+    io::{self, Read, Write},
+    process::{Command, Stdio},
+};
+
+const CTRL_C: u8 = 0x03;
+const CTRL_D: u8 = 0x04;
+const CTRL_U: u8 = 0x15;
+const CTRL_W: u8 = 0x17;
+const ESC: u8 = 0x1b;
+const BACKSPACE: u8 = 0x7f;
+
+/// Puts the terminal on stdin into raw mode through `stty`, restoring the previous settings
+/// when dropped
+struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        let saved = stty(&["-g"])?;
+        // Reads return after a tenth of a second without input, so a lone Esc can be told apart
+        // from the start of an arrow key sequence
+        stty(&["raw", "-echo", "min", "0", "time", "1"])?;
+        Ok(Self {
+            saved: saved.trim().to_string(),
+        })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other("stty failed to change the terminal mode"));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Reads one line in raw mode so it works in a bare popup terminal. Enter submits, Esc, Ctrl-C
+/// and Ctrl-D cancel, which returns `None`.
+pub fn read_line(prompt: &str) -> io::Result<Option<String>> {
+    let raw = RawMode::enable()?;
+    let mut stdin = io::stdin().lock();
+    let mut stdout = io::stdout().lock();
+    let mut line = String::new();
+    // Bytes of a character that has not fully arrived yet
+    let mut partial = Vec::new();
+
+    write!(stdout, "{prompt}")?;
+    stdout.flush()?;
+    let result = loop {
+        let Some(byte) = read_byte(&mut stdin)? else {
+            continue;
+        };
+        match byte {
+            b'\r' | b'\n' => break Some(line),
+            CTRL_C | CTRL_D => break None,
+            ESC => match read_byte(&mut stdin)? {
+                // Arrow and function keys, skipped up to their final byte
+                Some(b'[' | b'O') => {
+                    while read_byte(&mut stdin)?.is_some_and(|b| !(0x40..=0x7e).contains(&b)) {}
+                    continue;
+                }
+                _ => break None,
+            },
+            BACKSPACE | 0x08 => {
+                line.pop();
+            }
+            CTRL_U => line.clear(),
+            CTRL_W => {
+                let kept = line.trim_end().rfind(' ').map_or(0, |i| i + 1);
+                line.truncate(kept);
+            }
+            byte if byte < 0x20 => continue,
+            byte => {
+                partial.push(byte);
+                match std::str::from_utf8(&partial) {
+                    Ok(c) => line.push_str(c),
+                    // Wait for the rest of a multi-byte character
+                    Err(e) if e.error_len().is_none() => continue,
+                    Err(_) => {}
+                }
+                partial.clear();
+            }
+        }
+        // Redraw the whole line, simpler than tracking what changed
+        write!(stdout, "\r\x1b[K{prompt}{line}")?;
+        stdout.flush()?;
+    };
+
+    drop(raw);
+    writeln!(stdout)?;
+    Ok(result)
+}
+
+/// A byte, or `None` when nothing arrived within the read timeout
+fn read_byte(stdin: &mut impl Read) -> io::Result<Option<u8>> {
+    let mut byte = [0];
+    match stdin.read(&mut byte)? {
+        0 => Ok(None),
+        _ => Ok(Some(byte[0])),
+    }
+}
@@ -0,0 +1,213 @@
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use serde_json::{Map, Value};
+use taskr_core::{
+    integrity, persist,
+    store::{self, Task},
+};
+
+const HELP: &str = "\
+Commands:
+  list                      List raw records and whether they load
+  show <id>                 Print a record as stored
+  drop <id>                 Remove a record
+  set <id> <field> <value>  Set a field, the value is JSON or else taken as text
+  unset <id> <field>        Remove a field
+  check                     Check whether the whole file loads again
+  restore                   Replace everything with the .bak copy
+  save                      Write the records back to disk
+  quit                      Leave, discarding unsaved changes";
+
+/// Interactive session on the raw records of a tasks file that no longer loads normally
+pub struct Rescue {
+    path: PathBuf,
+    root: Value,
+    dirty: bool,
+}
+
+impl Rescue {
+    /// Reads `path` as plain JSON, without requiring it to be a valid store
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let json = fs::read_to_string(path).map_err(|e| format!("Failed to read {path:?}: {e}"))?;
+        let root = serde_json::from_str(&json).map_err(|e| {
+            format!("{path:?} is not JSON at all ({e}), it can only be restored from the backup")
+        })?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            root,
+            dirty: false,
+        })
+    }
+
+    /// Starts from the backup when the file itself is beyond repair
+    pub fn from_backup(path: &Path) -> Result<Self, String> {
+        let mut rescue = Self::open(&persist::backup_path(path))?;
+        rescue.path = path.to_path_buf();
+        rescue.dirty = true;
+        Ok(rescue)
+    }
+
+    pub fn run(&mut self) -> io::Result<()> {
+        println!("Rescue mode on {:?}, type 'help' for commands", self.path);
+        self.check();
+
+        let stdin = io::stdin();
+        loop {
+            print!("rescue> ");
+            io::stdout().flush()?;
+
+            let mut line = String::new();
+            if stdin.lock().read_line(&mut line)? == 0 {
+                break;
+            }
+            let mut words = line.split_whitespace();
+            let Some(command) = words.next() else {
+                continue;
+            };
+            let args = words.collect::<Vec<_>>();
+
+            let result = match (command, args.as_slice()) {
+                ("help", []) => {
+                    println!("{HELP}");
+                    Ok(())
+                }
+                ("list", []) => self.list(),
+                ("show", [id]) => self.show(id),
+                ("drop", [id]) => self.drop_record(id),
+                ("set", [id, field, value @ ..]) if !value.is_empty() => {
+                    self.set(id, field, &value.join(" "))
+                }
+                ("unset", [id, field]) => self.unset(id, field),
+                ("check", []) => {
+                    self.check();
+                    Ok(())
+                }
+                ("restore", []) => self.restore(),
+                ("save", []) => self.save(),
+                ("quit" | "exit", []) => break,
+                _ => Err(format!(
+                    "Unknown command: {}, type 'help' for commands",
+                    line.trim()
+                )),
+            };
+            if let Err(e) = result {
+                println!("{e}");
+            }
+        }
+
+        if self.dirty {
+            println!("Left without saving, {:?} is unchanged", self.path);
+        }
+        Ok(())
+    }
+
+    fn records(&mut self) -> Result<&mut Map<String, Value>, String> {
+        self.root
+            .get_mut("tasks")
+            .and_then(Value::as_object_mut)
+            .ok_or_else(|| "The file has no \"tasks\" object".to_string())
+    }
+
+    fn record(&mut self, id: &str) -> Result<&mut Map<String, Value>, String> {
+        self.records()?
+            .get_mut(id)
+            .ok_or_else(|| format!("No record with ID: {id}"))?
+            .as_object_mut()
+            .ok_or_else(|| format!("Record {id} is not an object"))
+    }
+
+    fn list(&mut self) -> Result<(), String> {
+        let records = self.records()?;
+
+        let mut ids = records.keys().cloned().collect::<Vec<_>>();
+        ids.sort_by_key(|id| (id.parse::<u64>().unwrap_or(u64::MAX), id.clone()));
+        for id in ids {
+            let record = &records[&id];
+            let description = record
+                .get("description")
+                .and_then(Value::as_str)
+                .unwrap_or("<no description>");
+            match serde_json::from_value::<Task>(record.clone()) {
+                Ok(_) => println!("  {id}: {description}"),
+                Err(e) => println!("! {id}: {description} ({e})"),
+            }
+        }
+        Ok(())
+    }
+
+    fn show(&mut self, id: &str) -> Result<(), String> {
+        let record = self.record(id)?;
+        let json = serde_json::to_string_pretty(record).expect("Failed to serialize record");
+        println!("{json}");
+        Ok(())
+    }
+
+    fn drop_record(&mut self, id: &str) -> Result<(), String> {
+        self.records()?
+            .remove(id)
+            .ok_or_else(|| format!("No record with ID: {id}"))?;
+        self.dirty = true;
+        println!("Dropped record {id}");
+        Ok(())
+    }
+
+    fn set(&mut self, id: &str, field: &str, value: &str) -> Result<(), String> {
+        let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.into()));
+        self.record(id)?.insert(field.to_string(), value);
+        self.dirty = true;
+        Ok(())
+    }
+
+    fn unset(&mut self, id: &str, field: &str) -> Result<(), String> {
+        self.record(id)?
+            .remove(field)
+            .ok_or_else(|| format!("Record {id} has no field {field}"))?;
+        self.dirty = true;
+        Ok(())
+    }
+
+    fn check(&self) {
+        match store::validate(&self.root) {
+            Ok(()) => println!("The file loads, save and leave to use it normally"),
+            Err(e) => println!("The file does not load yet: {e}"),
+        }
+    }
+
+    fn restore(&mut self) -> Result<(), String> {
+        *self = Self::from_backup(&self.path)?;
+        println!("Loaded the backup, save to keep it");
+        self.check();
+        Ok(())
+    }
+
+    /// Keeps the damaged original as .broken the first time, so nothing is ever lost
+    fn save(&mut self) -> Result<(), String> {
+        let broken = self.path.with_extension("json.broken");
+        if self.path.exists() && !broken.exists() {
+            fs::copy(&self.path, &broken)
+                .map_err(|e| format!("Failed to keep a copy at {broken:?}: {e}"))?;
+            println!("Kept the damaged file as {broken:?}");
+        }
+
+        let json = serde_json::to_string_pretty(&self.root).expect("Failed to serialize records");
+        let sidecar_error = |e| {
+            format!(
+                "Failed to write {:?}: {e}",
+                integrity::sidecar_path(&self.path)
+            )
+        };
+        integrity::expect_checksum(&self.path, json.as_bytes()).map_err(sidecar_error)?;
+        persist::write_atomic(&self.path, json.as_bytes())
+            .map_err(|e| format!("Failed to write {:?}: {e}", self.path))?;
+        integrity::write_checksum(&self.path, json.as_bytes()).map_err(sidecar_error)?;
+
+        self.dirty = false;
+        println!("Saved {:?}", self.path);
+        self.check();
+        Ok(())
+    }
+}
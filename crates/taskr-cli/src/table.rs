@@ -0,0 +1,271 @@
+use std::{
+    env,
+    io::{self, IsTerminal},
+    iter,
+    process::{Command, Stdio},
+};
+
+const GAP: &str = "  ";
+/// The shrinking column is never cut down further than this
+const MIN_FLEX_WIDTH: usize = 10;
+const ELLIPSIS: char = '…';
+const RESET: &str = "\x1b[0m";
+
+/// Column-aligned rows. One column can be marked as flexible, it is cut short to fit the width
+/// the table is rendered at.
+pub struct Table {
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+    flex: Option<usize>,
+}
+
+impl Table {
+    pub fn new(headers: Vec<&'static str>) -> Self {
+        Self {
+            headers,
+            rows: Vec::new(),
+            flex: None,
+        }
+    }
+
+    /// The column to truncate when the table is too wide
+    pub fn flex(mut self, column: usize) -> Self {
+        self.flex = Some(column);
+        self
+    }
+
+    pub fn push(&mut self, row: Vec<String>) {
+        debug_assert_eq!(
+            row.len(),
+            self.headers.len(),
+            "Row does not match the headers"
+        );
+        self.rows.push(row);
+    }
+
+    /// Renders the table, fitting it into `width` columns when there is a limit
+    pub fn render(&self, width: Option<usize>) -> String {
+        let mut widths = self
+            .headers
+            .iter()
+            .map(|header| display_width(header))
+            .collect::<Vec<_>>();
+        for row in &self.rows {
+            for (column, cell) in row.iter().enumerate() {
+                widths[column] = widths[column].max(display_width(cell));
+            }
+        }
+
+        if let (Some(width), Some(flex)) = (width, self.flex) {
+            let total = widths.iter().sum::<usize>() + GAP.len() * (widths.len() - 1);
+            if total > width {
+                let others = total - widths[flex];
+                widths[flex] = width.saturating_sub(others).max(MIN_FLEX_WIDTH);
+            }
+        }
+
+        let headers = self
+            .headers
+            .iter()
+            .map(|header| header.to_string())
+            .collect();
+        let mut output = String::new();
+        for row in iter::once(headers).chain(self.rows.iter().cloned()) {
+            let last = row.len() - 1;
+            let mut line = String::new();
+            for (column, cell) in row.iter().enumerate() {
+                let cell = truncate(cell, widths[column]);
+                line.push_str(&cell);
+                if column < last {
+                    let padding = widths[column] - display_width(&cell);
+                    line.push_str(&" ".repeat(padding));
+                    line.push_str(GAP);
+                }
+            }
+            output.push_str(line.trim_end());
+            output.push('\n');
+        }
+        output
+    }
+}
+
+/// Width of the terminal stdout is printed to, `None` when it is not a terminal
+pub fn terminal_width() -> Option<usize> {
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+    if let Some(columns) = env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        return Some(columns);
+    }
+    // stty reports the size of the terminal on its stdin, which is usually the same one
+    let output = Command::new("stty")
+        .arg("size")
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let size = String::from_utf8(output.stdout).ok()?;
+    size.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// Columns `text` takes up in a terminal. Escape sequences such as highlighting do not count,
+/// wide characters like CJK and emoji count twice.
+pub fn display_width(text: &str) -> usize {
+    visible_chars(text)
+        .filter(|(_, visible)| *visible)
+        .map(|(c, _)| char_width(c))
+        .sum()
+}
+
+/// Columns a character takes up, following the east asian width of the common ranges
+fn char_width(c: char) -> usize {
+    match c as u32 {
+        // Combining marks, zero width spaces and joiners, variation selectors
+        0x0300..=0x036F
+        | 0x1AB0..=0x1AFF
+        | 0x1DC0..=0x1DFF
+        | 0x200B..=0x200F
+        | 0x2060..=0x2064
+        | 0x20D0..=0x20FF
+        | 0xFE00..=0xFE0F
+        | 0xFE20..=0xFE2F
+        | 0xFEFF => 0,
+        _ if c.is_control() => 0,
+        // Hangul Jamo, CJK, Hangul syllables, fullwidth forms, emoji
+        0x1100..=0x115F
+        | 0x231A..=0x231B
+        | 0x23E9..=0x23EC
+        | 0x2614..=0x2615
+        | 0x26A1
+        | 0x26AA..=0x26AB
+        | 0x26BD..=0x26BE
+        | 0x26C4..=0x26C5
+        | 0x26D4
+        | 0x26EA
+        | 0x26F2..=0x26F5
+        | 0x26FA
+        | 0x26FD
+        | 0x2705
+        | 0x270A..=0x270B
+        | 0x2728
+        | 0x274C
+        | 0x2753..=0x2755
+        | 0x2757
+        | 0x2795..=0x2797
+        | 0x2B1B..=0x2B1C
+        | 0x2B50
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xA960..=0xA97F
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE10..=0xFE19
+        | 0xFE30..=0xFE6F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F004
+        | 0x1F0CF
+        | 0x1F18E
+        | 0x1F191..=0x1F19A
+        | 0x1F1E6..=0x1F1FF
+        | 0x1F200..=0x1F251
+        | 0x1F300..=0x1F64F
+        | 0x1F680..=0x1F6FF
+        | 0x1F7E0..=0x1F7EB
+        | 0x1F90C..=0x1F9FF
+        | 0x1FA70..=0x1FAFF
+        | 0x20000..=0x2FFFD
+        | 0x30000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+/// Cuts `text` down to `width` columns, ending it with an ellipsis when cut
+fn truncate(text: &str, width: usize) -> String {
+    if display_width(text) <= width {
+        return text.to_string();
+    }
+
+    let mut truncated = String::new();
+    let mut used = 0;
+    let mut escaped = false;
+    for (c, visible) in visible_chars(text) {
+        if !visible {
+            escaped = true;
+            truncated.push(c);
+            continue;
+        }
+        // Leave a column for the ellipsis
+        let char_width = char_width(c);
+        if used + char_width >= width {
+            break;
+        }
+        truncated.push(c);
+        used += char_width;
+    }
+    truncated.push(ELLIPSIS);
+    // A highlight cut in half would otherwise bleed into the next columns
+    if escaped {
+        truncated.push_str(RESET);
+    }
+    truncated
+}
+
+/// Every character along with whether it is visible, i.e. not part of an escape sequence
+fn visible_chars(text: &str) -> impl Iterator<Item = (char, bool)> {
+    let mut in_escape = false;
+    text.chars().map(move |c| {
+        if c == '\x1b' {
+            in_escape = true;
+            return (c, false);
+        }
+        if in_escape {
+            // Sequences like \x1b[7m end with a letter
+            if c.is_ascii_alphabetic() {
+                in_escape = false;
+            }
+            return (c, false);
+        }
+        (c, true)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wide_characters_count_twice() {
+        assert_eq!(display_width("task"), 4);
+        assert_eq!(display_width("買い物"), 6);
+        assert_eq!(display_width("회의"), 4);
+        assert_eq!(display_width("ship 🚀"), 7);
+        assert_eq!(display_width("cafe\u{301}"), 4);
+        assert_eq!(display_width("\x1b[7m日本\x1b[0m"), 4);
+    }
+
+    #[test]
+    fn truncates_by_columns() {
+        assert_eq!(truncate("日本語のタスク", 6), "日本…");
+        // The ellipsis would land in the middle of a wide character
+        assert_eq!(truncate("日本語のタスク", 7), "日本語…");
+        assert_eq!(truncate("ab日本", 4), "ab…");
+        assert_eq!(truncate("日本", 4), "日本");
+    }
+
+    #[test]
+    fn aligns_wide_cells() {
+        let mut table = Table::new(vec!["ID", "Description", "Status"]);
+        table.push(vec!["1".into(), "買い物".into(), "Todo".into()]);
+        table.push(vec!["2".into(), "groceries".into(), "Done".into()]);
+        let rendered = table.render(None);
+        let columns = rendered
+            .lines()
+            .map(|line| display_width(&line[..line.rfind("  ").unwrap()]))
+            .collect::<Vec<_>>();
+        assert_eq!(columns, [15, 15, 15]);
+    }
+}
@@ -0,0 +1,64 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Directory in the config dir holding one `<name>.txt` file per template
+pub const TEMPLATES_DIR: &str = "templates";
+
+pub fn template_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(TEMPLATES_DIR).join(format!("{name}.txt"))
+}
+
+pub fn read(dir: &Path, name: &str) -> io::Result<String> {
+    fs::read_to_string(template_path(dir, name))
+}
+
+/// Names of the `{name?}` placeholders in `text`, each once and in order of appearance
+pub fn placeholders(text: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    for (_, name, _) in scan(text) {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// Replaces every placeholder with its value, placeholders without one are left as they are
+pub fn expand(text: &str, values: &HashMap<String, String>) -> String {
+    let mut expanded = String::with_capacity(text.len());
+    let mut rest = 0;
+    for (start, name, end) in scan(text) {
+        if let Some(value) = values.get(name) {
+            expanded.push_str(&text[rest..start]);
+            expanded.push_str(value);
+            rest = end;
+        }
+    }
+    expanded.push_str(&text[rest..]);
+    expanded
+}
+
+/// Placeholders as their start, name and end. Braces around anything else, e.g. `{ui}` without
+/// the question mark, are plain text.
+fn scan(text: &str) -> Vec<(usize, &str, usize)> {
+    let mut found = Vec::new();
+    let mut offset = 0;
+    while let Some(open) = text[offset..].find('{') {
+        let start = offset + open;
+        let after = &text[start + 1..];
+        let name_len = after
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(after.len());
+        if name_len > 0 && after[name_len..].starts_with("?}") {
+            let end = start + 1 + name_len + 2;
+            found.push((start, &after[..name_len], end));
+            offset = end;
+        } else {
+            offset = start + 1;
+        }
+    }
+    found
+}
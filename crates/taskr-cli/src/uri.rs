@@ -0,0 +1,226 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    path::Path,
+};
+
+use crate::utils::APPNAME;
+
+pub const SCHEME: &str = "taskr";
+
+/// Actions a URI can ask for, with the parameters each requires
+const ACTIONS: &[(&str, &[&str])] = &[("add", &["description"])];
+
+/// A parsed `taskr://<action>?key=value&...` URI
+#[derive(Debug)]
+pub struct TaskUri {
+    pub action: String,
+    pub params: Vec<(String, String)>,
+}
+
+impl TaskUri {
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+#[derive(Debug)]
+pub enum UriError {
+    WrongScheme(String),
+    MissingAction,
+    UnknownAction(String),
+    MissingParam(String, &'static str),
+    InvalidEncoding(String),
+}
+
+impl Display for UriError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WrongScheme(uri) => write!(f, "Not a {SCHEME}:// URI: {uri}"),
+            Self::MissingAction => {
+                write!(f, "URI has no action, e.g. {SCHEME}://add?description=...")
+            }
+            Self::UnknownAction(action) => write!(f, "Unsupported URI action: {action}"),
+            Self::MissingParam(action, param) => {
+                write!(f, "{SCHEME}://{action} needs a {param} parameter")
+            }
+            Self::InvalidEncoding(part) => write!(f, "Invalid percent-encoding in: {part}"),
+        }
+    }
+}
+
+impl Error for UriError {}
+
+/// Reads a URI for one of the supported actions, checking that it has the parameters the action
+/// requires
+pub fn parse(uri: &str) -> Result<TaskUri, UriError> {
+    let rest = uri
+        .strip_prefix(SCHEME)
+        .and_then(|rest| rest.strip_prefix("://"))
+        .ok_or_else(|| UriError::WrongScheme(uri.to_string()))?;
+
+    let (action, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let action = action.trim_end_matches('/').to_lowercase();
+    if action.is_empty() {
+        return Err(UriError::MissingAction);
+    }
+    let Some((_, required)) = ACTIONS.iter().find(|(name, _)| *name == action) else {
+        return Err(UriError::UnknownAction(action));
+    };
+
+    let params = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            Ok((percent_decode(key)?, percent_decode(value)?))
+        })
+        .collect::<Result<Vec<_>, UriError>>()?;
+
+    let uri = TaskUri { action, params };
+    if let Some(missing) = required.iter().find(|param| uri.param(param).is_none()) {
+        return Err(UriError::MissingParam(uri.action, missing));
+    }
+    Ok(uri)
+}
+
+/// Decodes `%XX` escapes and `+` as a space, as produced by browsers for query strings
+fn percent_decode(input: &str) -> Result<String, UriError> {
+    let invalid = || UriError::InvalidEncoding(input.to_string());
+
+    let mut bytes = Vec::with_capacity(input.len());
+    let mut iter = input.bytes();
+    while let Some(byte) = iter.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = [
+                    iter.next().ok_or_else(invalid)?,
+                    iter.next().ok_or_else(invalid)?,
+                ];
+                // from_str_radix would take a sign, e.g. %+1
+                if !hex.iter().all(u8::is_ascii_hexdigit) {
+                    return Err(invalid());
+                }
+                let hex = std::str::from_utf8(&hex).expect("Hex digits are ASCII");
+                bytes.push(u8::from_str_radix(hex, 16).expect("Two hex digits fit a byte"));
+            }
+            _ => bytes.push(byte),
+        }
+    }
+
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+/// Desktop entry registering `exe` as the handler for taskr:// URIs
+pub fn desktop_entry(exe: &Path) -> String {
+    format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name={APPNAME}\n\
+         Comment=Create tasks from {SCHEME}:// links\n\
+         Exec={} handle-uri %u\n\
+         Terminal=false\n\
+         NoDisplay=true\n\
+         MimeType=x-scheme-handler/{SCHEME};\n",
+        quote_exec_arg(&exe.to_string_lossy())
+    )
+}
+
+/// Quotes an argument of the `Exec` key as the Desktop Entry spec asks. Inside the double quotes
+/// `"`, `` ` ``, `$` and `\` are escaped with a backslash, and since the value is also a string,
+/// each backslash is then escaped again. `%` starts a field code, so it is doubled.
+fn quote_exec_arg(arg: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in arg.chars() {
+        match c {
+            '"' | '`' | '$' => quoted.extend(['\\', '\\', c]),
+            '\\' => quoted.push_str(r"\\\\"),
+            '%' => quoted.push_str("%%"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_and_plus_are_decoded() {
+        let uri = parse("taskr://Add/?description=Buy+milk%20%26%20eggs&due=tomorrow").unwrap();
+        assert_eq!(uri.action, "add");
+        assert_eq!(uri.param("description"), Some("Buy milk & eggs"));
+        assert_eq!(uri.param("due"), Some("tomorrow"));
+
+        assert_eq!(percent_decode("caf%C3%A9").unwrap(), "café");
+        assert_eq!(percent_decode("%2B1+%2b1").unwrap(), "+1 +1");
+    }
+
+    #[test]
+    fn invalid_escapes_are_errors() {
+        for input in ["%", "%4", "abc%", "%zz", "%+1", "%-1", "%C3"] {
+            assert!(
+                matches!(percent_decode(input), Err(UriError::InvalidEncoding(_))),
+                "{input}"
+            );
+        }
+        assert!(matches!(
+            parse("taskr://add?description=50%"),
+            Err(UriError::InvalidEncoding(_))
+        ));
+    }
+
+    #[test]
+    fn unknown_or_missing_actions() {
+        assert!(matches!(
+            parse("taskr://delete?id=1"),
+            Err(UriError::UnknownAction(action)) if action == "delete"
+        ));
+        assert!(matches!(
+            parse("taskr://?description=x"),
+            Err(UriError::MissingAction)
+        ));
+        assert!(matches!(
+            parse("https://add?description=x"),
+            Err(UriError::WrongScheme(_))
+        ));
+    }
+
+    #[test]
+    fn add_needs_a_description() {
+        assert!(matches!(
+            parse("taskr://add?due=tomorrow"),
+            Err(UriError::MissingParam(action, "description")) if action == "add"
+        ));
+        // Only a missing one, handle-uri rejects an empty one after trimming
+        assert_eq!(
+            parse("taskr://add?description=")
+                .unwrap()
+                .param("description"),
+            Some("")
+        );
+    }
+
+    #[test]
+    fn plain_paths_are_quoted() {
+        let entry = desktop_entry(Path::new("/usr/bin/taskr"));
+        assert!(entry.contains("\nExec=\"/usr/bin/taskr\" handle-uri %u\n"));
+    }
+
+    #[test]
+    fn reserved_characters_are_escaped() {
+        assert_eq!(
+            quote_exec_arg("/home/a b/My \"Apps\"/taskr"),
+            r#""/home/a b/My \\"Apps\\"/taskr""#
+        );
+        assert_eq!(quote_exec_arg("/opt/$x/`y`"), r#""/opt/\\$x/\\`y\\`""#);
+        assert_eq!(quote_exec_arg(r"C:\taskr"), r#""C:\\\\taskr""#);
+        assert_eq!(quote_exec_arg("/opt/100%/taskr"), r#""/opt/100%%/taskr""#);
+    }
+}
@@ -0,0 +1,520 @@
+use std::{
+    env,
+    error::Error,
+    fmt::{self, Display},
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use taskr_core::{persist, store::TaskStatus};
+use time::format_description;
+
+use crate::{
+    cli::{ColorMode, SortKey},
+    color::Theme,
+    limits,
+};
+
+pub const APPNAME: &str = "taskly";
+pub const CONFIG_FILE: &str = "config.toml";
+pub const CONFIG_KEYS: [&str; 16] = [
+    "sort",
+    "status",
+    "date_format",
+    "color",
+    "data_dir",
+    "rollover",
+    "max_open",
+    "max_store_size",
+    "theme_todo",
+    "theme_complete",
+    "theme_someday",
+    "theme_other",
+    "theme_overdue",
+    "theme_high",
+    "theme_critical",
+    "theme_match",
+];
+
+/// Identifies the shell session temporary tasks belong to. Without it the parent process, usually
+/// the shell, stands in for the session.
+pub const SESSION_ENV: &str = "TASKLY_SESSION";
+
+/// Directory holding tasks and the other state files, overrides the config and XDG state dir
+pub const DATA_DIR_ENV: &str = "TASKLY_DATA_DIR";
+
+pub enum Dirs {
+    Bin,     // Executable dir
+    Cache,   // Might need in the future
+    Config,  //
+    Data,    //
+    Home,    // Just $HOME
+    Runtime, // For IPC socket
+    State,   //
+}
+
+#[derive(Debug)]
+pub enum DirError {
+    DoesNotExist(PathBuf),
+    InvalidPath(PathBuf),
+    IoError(io::Error),
+    MissingVar(String),
+}
+
+impl Display for DirError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DoesNotExist(p) => writeln!(f, "Directory does not exist: {p:?}"),
+            Self::InvalidPath(p) => writeln!(f, "Invalid path: {p:?}"),
+            Self::IoError(io_err) => writeln!(f, "I/O error: {io_err}"),
+            Self::MissingVar(var) => writeln!(f, "Missing environment variable: {var}"),
+        }
+    }
+}
+
+impl Error for DirError {}
+
+/// Settings from config.toml, anything unset keeps the built-in behaviour
+#[derive(Debug, Default)]
+pub struct Config {
+    /// Order of `list` when no `--sort` is given
+    pub sort: Option<SortKey>,
+    /// Status `list` shows when none is given
+    pub status: Option<TaskStatus>,
+    /// A time format description, e.g. `[year]-[month]-[day] [hour]:[minute]`
+    pub date_format: Option<String>,
+    /// Color in output, `true` and `false` stand for auto and never
+    pub color: Option<ColorMode>,
+    /// Where tasks and the other state files are kept instead of the XDG state directory
+    pub data_dir: Option<PathBuf>,
+    /// Show open tasks from an earlier due day as rolled over to today instead of overdue
+    pub rollover: Option<bool>,
+    /// Open tasks above which a warning suggests cleaning up, 0 turns the warning off
+    pub max_open: Option<u64>,
+    /// Size of tasks.json above which a warning suggests archiving, e.g. `"2M"`
+    pub max_store_size: Option<u64>,
+    /// Set with the `theme_*` keys, e.g. `theme_overdue = "bold red"`
+    pub theme: Theme,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, io::Error),
+    /// A line that could not be read, with its number
+    Syntax(PathBuf, usize, String),
+    Invalid(PathBuf, String),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(p, io_err) => write!(f, "I/O error on {p:?}: {io_err}"),
+            Self::Syntax(p, line, message) => write!(f, "{p:?} line {line}: {message}"),
+            Self::Invalid(p, message) => write!(f, "Invalid setting in {p:?}: {message}"),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+/// Only the part of TOML the settings need is understood: top level keys with string, boolean or
+/// non-negative integer values
+enum Value {
+    String(String),
+    Bool(bool),
+    Integer(u64),
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::String(s) => write!(f, "{s}"),
+            Self::Bool(b) => write!(f, "{b}"),
+            Self::Integer(n) => write!(f, "{n}"),
+        }
+    }
+}
+
+impl Config {
+    fn set(&mut self, key: &str, value: Value) -> Result<(), String> {
+        match (key, value) {
+            ("sort", Value::String(sort)) => {
+                self.sort = Some(SortKey::from_str(&sort, true).map_err(|_| {
+                    let keys = SortKey::value_variants()
+                        .iter()
+                        .filter_map(|key| Some(key.to_possible_value()?.get_name().to_string()))
+                        .collect::<Vec<_>>();
+                    format!("unknown sort '{sort}', try one of {}", keys.join(", "))
+                })?);
+            }
+            ("status", Value::String(status)) => self.status = Some(status.parse()?),
+            ("date_format", Value::String(format)) => {
+                format_description::parse(&format)
+                    .map_err(|e| format!("invalid date_format '{format}': {e}"))?;
+                self.date_format = Some(format);
+            }
+            ("color", Value::Bool(color)) => {
+                self.color = Some(if color {
+                    ColorMode::Auto
+                } else {
+                    ColorMode::Never
+                });
+            }
+            ("color", Value::String(mode)) => {
+                self.color = Some(ColorMode::from_str(&mode, true).map_err(|_| {
+                    format!("unknown color '{mode}', try auto, always, never, true or false")
+                })?);
+            }
+            ("data_dir", Value::String(dir)) => {
+                let dir = PathBuf::from(dir);
+                if !dir.is_absolute() {
+                    return Err(format!("data_dir {dir:?} has to be an absolute path"));
+                }
+                self.data_dir = Some(dir);
+            }
+            ("rollover", Value::Bool(rollover)) => self.rollover = Some(rollover),
+            ("rollover", _) => return Err("rollover has to be true or false".to_string()),
+            ("max_open" | "max_store_size", Value::Integer(limit)) => {
+                let slot = match key {
+                    "max_open" => &mut self.max_open,
+                    _ => &mut self.max_store_size,
+                };
+                *slot = Some(limit);
+            }
+            ("max_open", Value::String(limit)) => {
+                self.max_open =
+                    Some(limit.trim().parse().map_err(|_| {
+                        format!("max_open '{limit}' has to be a number such as 500")
+                    })?);
+            }
+            ("max_store_size", Value::String(size)) => {
+                self.max_store_size = Some(limits::parse_size(&size).ok_or_else(|| {
+                    format!("max_store_size '{size}' has to be a size such as 512K or 2M")
+                })?);
+            }
+            ("max_open" | "max_store_size", _) => {
+                return Err(format!("{key} has to be a number"));
+            }
+            (key, Value::String(style)) if key.starts_with("theme_") => {
+                let theme = &mut self.theme;
+                let slot = match key {
+                    "theme_todo" => &mut theme.todo,
+                    "theme_complete" => &mut theme.complete,
+                    "theme_someday" => &mut theme.someday,
+                    "theme_other" => &mut theme.other,
+                    "theme_overdue" => &mut theme.overdue,
+                    "theme_high" => &mut theme.high,
+                    "theme_critical" => &mut theme.critical,
+                    "theme_match" => &mut theme.matched,
+                    _ => {
+                        return Err(format!(
+                            "unknown setting '{key}', known ones are {}",
+                            CONFIG_KEYS.join(", ")
+                        ));
+                    }
+                };
+                *slot = style.parse()?;
+            }
+            (key, _) if CONFIG_KEYS.contains(&key) => {
+                return Err(format!("{key} has to be a quoted string"));
+            }
+            (key, _) => {
+                return Err(format!(
+                    "unknown setting '{key}', known ones are {}",
+                    CONFIG_KEYS.join(", ")
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `config.toml` in the config directory, which may not exist yet
+pub fn config_path() -> Result<PathBuf, DirError> {
+    Ok(get_dir(Dirs::Config)?.join(APPNAME).join(CONFIG_FILE))
+}
+
+/// Reads the settings at `path`, a missing file means none are set
+pub fn load_config(path: &Path) -> Result<Config, ConfigError> {
+    parse_config(path, &read_config(path)?)
+}
+
+/// The value of `key` as set in the file, `None` when it is not set
+pub fn get_config_value(path: &Path, key: &str) -> Result<Option<String>, ConfigError> {
+    if !CONFIG_KEYS.contains(&key) {
+        let known = CONFIG_KEYS.join(", ");
+        let message = format!("unknown setting '{key}', known ones are {known}");
+        return Err(ConfigError::Invalid(path.to_path_buf(), message));
+    }
+
+    let text = read_config(path)?;
+    parse_config(path, &text)?;
+    let value = text
+        .lines()
+        .rev()
+        .filter_map(|line| parse_toml_line(line).ok().flatten())
+        .find(|(k, _)| k == key)
+        .map(|(_, value)| value.to_string());
+    Ok(value)
+}
+
+/// Sets `key` in the file, replacing the line it was set on so comments and the order of the
+/// other settings stay as they are
+pub fn set_config_value(path: &Path, key: &str, value: &str) -> Result<(), ConfigError> {
+    let text = read_config(path)?;
+    let value = match key {
+        "color" | "rollover" => match value {
+            "true" | "on" | "yes" => "true".to_string(),
+            "false" | "off" | "no" => "false".to_string(),
+            _ => quote_toml(value),
+        },
+        "max_open" | "max_store_size" if value.parse::<u64>().is_ok() => value.to_string(),
+        _ => quote_toml(value),
+    };
+    let line = format!("{key} = {value}");
+
+    let mut replaced = false;
+    let mut lines = Vec::new();
+    for existing in text.lines() {
+        let is_key = matches!(parse_toml_line(existing), Ok(Some((k, _))) if k == key);
+        if !is_key {
+            lines.push(existing.to_string());
+        } else if !replaced {
+            lines.push(line.clone());
+            replaced = true;
+        }
+    }
+    if !replaced {
+        lines.push(line);
+    }
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+
+    // Checks the new value along with everything else before anything is written
+    parse_config(path, &updated)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| ConfigError::Io(dir.to_path_buf(), e))?;
+    }
+    persist::write_atomic(path, updated.as_bytes())
+        .map_err(|e| ConfigError::Io(path.to_path_buf(), e))
+}
+
+fn read_config(path: &Path) -> Result<String, ConfigError> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(text),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(ConfigError::Io(path.to_path_buf(), e)),
+    }
+}
+
+fn parse_config(path: &Path, text: &str) -> Result<Config, ConfigError> {
+    let mut config = Config::default();
+    for (i, line) in text.lines().enumerate() {
+        let parsed = parse_toml_line(line)
+            .map_err(|message| ConfigError::Syntax(path.to_path_buf(), i + 1, message))?;
+        if let Some((key, value)) = parsed {
+            config
+                .set(&key, value)
+                .map_err(|message| ConfigError::Invalid(path.to_path_buf(), message))?;
+        }
+    }
+    Ok(config)
+}
+
+/// A `key = value` pair, `None` for blank and comment lines
+fn parse_toml_line(line: &str) -> Result<Option<(String, Value)>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    if line.starts_with('[') {
+        return Err("tables are not supported, settings go at the top level".to_string());
+    }
+
+    let (key, value) = line
+        .split_once('=')
+        .ok_or_else(|| "expected key = value".to_string())?;
+    let key = key.trim();
+    let bare = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+    if key.is_empty() || !key.chars().all(bare) {
+        return Err(format!("invalid key '{key}'"));
+    }
+
+    let (value, rest) = parse_toml_value(value.trim())?;
+    let rest = rest.trim();
+    if !rest.is_empty() && !rest.starts_with('#') {
+        return Err(format!("unexpected '{rest}' after the value of {key}"));
+    }
+    Ok(Some((key.to_string(), value)))
+}
+
+/// The value at the start of `s` and what follows it
+fn parse_toml_value(s: &str) -> Result<(Value, &str), String> {
+    if let Some(rest) = s.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = rest.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Ok((Value::String(value), &rest[i + 1..])),
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some(c @ ('"' | '\\')) => value.push(c),
+                    Some(c) => return Err(format!("unsupported escape '\\{c}'")),
+                    None => break,
+                },
+                c => value.push(c),
+            }
+        }
+        return Err("unclosed string".to_string());
+    }
+    // Literal strings have no escapes
+    if let Some(rest) = s.strip_prefix('\'') {
+        let end = rest
+            .find('\'')
+            .ok_or_else(|| "unclosed string".to_string())?;
+        return Ok((Value::String(rest[..end].to_string()), &rest[end + 1..]));
+    }
+    if let Some(rest) = s.strip_prefix("true") {
+        return Ok((Value::Bool(true), rest));
+    }
+    if let Some(rest) = s.strip_prefix("false") {
+        return Ok((Value::Bool(false), rest));
+    }
+    let digits = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    if digits > 0 {
+        let number = s[..digits]
+            .parse()
+            .map_err(|_| format!("{} is too large", &s[..digits]))?;
+        return Ok((Value::Integer(number), &s[digits..]));
+    }
+    Err(format!(
+        "expected a quoted string, true, false or a number, found '{s}'"
+    ))
+}
+
+fn quote_toml(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t");
+    format!("\"{escaped}\"")
+}
+
+pub fn session_id() -> String {
+    env::var(SESSION_ENV)
+        .ok()
+        .filter(|session| !session.is_empty())
+        .unwrap_or_else(|| std::os::unix::process::parent_id().to_string())
+}
+
+// Lil helper for get_dir function(s)
+fn get_xdg_path(env_var: &str, default: impl FnOnce() -> PathBuf) -> PathBuf {
+    env::var_os(env_var)
+        .map(PathBuf::from)
+        .and_then(|p| p.is_absolute().then_some(p))
+        .unwrap_or_else(default)
+}
+
+/// Used for getting a directory directly
+pub fn get_dir(dir: Dirs) -> Result<PathBuf, DirError> {
+    let home_dir = match env::var_os("HOME").map(PathBuf::from) {
+        Some(p) if p.is_absolute() => p,
+        Some(p) => return Err(DirError::InvalidPath(p)),
+        None => return Err(DirError::MissingVar("HOME".into())),
+    };
+
+    let base_dir_path = match dir {
+        Dirs::Home => home_dir,
+        Dirs::Bin => get_xdg_path("XDG_BIN_HOME", || home_dir.join(".local/bin")),
+        Dirs::Cache => get_xdg_path("XDG_CACHE_HOME", || home_dir.join(".cache")),
+        Dirs::Config => get_xdg_path("XDG_CONFIG_HOME", || home_dir.join(".config")),
+        Dirs::Data => get_xdg_path("XDG_DATA_HOME", || home_dir.join(".local/share")),
+        Dirs::Runtime => env::var_os("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .and_then(|p| p.is_absolute().then_some(p))
+            .ok_or_else(|| DirError::MissingVar("XDG_RUNTIME_DIR".into()))?,
+        Dirs::State => get_xdg_path("XDG_STATE_HOME", || home_dir.join(".local/state")),
+    };
+
+    Ok(base_dir_path)
+}
+
+/// Used for getting a directory with an APPNAME directory at the end
+pub fn get_app_dir(dir: Dirs) -> Result<PathBuf, DirError> {
+    let path = get_dir(dir)?.join(APPNAME);
+    if !path.exists() {
+        return Err(DirError::DoesNotExist(path));
+    }
+    Ok(path)
+}
+
+/// Where tasks are kept: the `--data-dir` flag, then `TASKLY_DATA_DIR`, then `data_dir` from the
+/// config and otherwise the state directory. It is created when missing.
+pub fn get_data_dir(flag: Option<&Path>, config: &Config) -> Result<PathBuf, DirError> {
+    let env = env::var_os(DATA_DIR_ENV)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from);
+    let dir = match flag.map(Path::to_path_buf).or(env) {
+        // Relative to where taskly was started, a later change of directory must not move it
+        Some(dir) => std::path::absolute(&dir).map_err(DirError::IoError)?,
+        None => match &config.data_dir {
+            Some(dir) => dir.clone(),
+            None => match get_app_dir(Dirs::State) {
+                Ok(dir) => return Ok(dir),
+                Err(DirError::DoesNotExist(dir)) => dir,
+                Err(e) => return Err(e),
+            },
+        },
+    };
+    if !dir.exists() {
+        fs::create_dir_all(&dir).map_err(DirError::IoError)?;
+    }
+    Ok(dir)
+}
+
+/// Used for getting a directory directly
+pub fn get_dir_with<P: AsRef<Path>>(dir: Dirs, append_dir: P) -> Result<PathBuf, DirError> {
+    let path = get_dir(dir)?.join(append_dir);
+    if !path.exists() {
+        return Err(DirError::DoesNotExist(path));
+    }
+    Ok(path)
+}
+
+pub fn get_app_dir_with<P: AsRef<Path>>(dir: Dirs, append_dir: P) -> Result<PathBuf, DirError> {
+    let path = get_dir(dir)?.join(append_dir).join(APPNAME);
+    if !path.exists() {
+        return Err(DirError::DoesNotExist(path));
+    }
+    Ok(path)
+}
+
+pub fn get_config_file<P: AsRef<Path>>(filename: P) -> Result<PathBuf, DirError> {
+    let config_dir = match get_app_dir(Dirs::Config) {
+        Ok(p) => p,
+        Err(DirError::DoesNotExist(path)) => {
+            fs::create_dir_all(&path).map_err(DirError::IoError)?;
+            path
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            return Err(e);
+        }
+    };
+    if !config_dir.exists() {
+        println!("Config directory does not exist");
+        println!("Created config directory at: {:?}", config_dir);
+        fs::create_dir_all(&config_dir).map_err(DirError::IoError)?;
+    }
+    let config_file = config_dir.join(filename);
+    if !config_file.exists() {
+        println!("Config file does not exist");
+        println!("Created config file at: {:?}", config_file);
+        File::create(&config_file).map_err(DirError::IoError)?;
+    }
+    Ok(config_file)
+}
@@ -0,0 +1,136 @@
+use std::{
+    fmt::{self, Display},
+    path::PathBuf,
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+
+const CONDITION_EXAMPLES: &str = "\"after 2025-07-01T00:00:00Z\", \"file PATH\" or \"url URL\"";
+
+/// Something outside taskly a blocked task waits for, stored as its text form, e.g.
+/// `url https://example.com/status`
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Condition {
+    /// The time has passed
+    After(OffsetDateTime),
+    /// The file exists
+    File(PathBuf),
+    /// A GET request returns 200
+    Url(String),
+}
+
+impl Condition {
+    /// Whether the condition holds now. URLs are left to `check_url`, as the core does no
+    /// networking. An error means the condition could not be checked at all.
+    pub fn is_met(
+        &self,
+        now: OffsetDateTime,
+        check_url: impl FnOnce(&str) -> Result<bool, String>,
+    ) -> Result<bool, String> {
+        match self {
+            Self::After(time) => Ok(now > *time),
+            Self::File(path) => Ok(path.exists()),
+            Self::Url(url) => check_url(url),
+        }
+    }
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid condition '{s}', try {CONDITION_EXAMPLES}");
+
+        let (kind, value) = s.trim().split_once(' ').ok_or_else(invalid)?;
+        let value = value.trim();
+        match kind {
+            "after" => OffsetDateTime::parse(value, &Rfc3339)
+                .map(Self::After)
+                .map_err(|_| invalid()),
+            "file" => Ok(Self::File(PathBuf::from(value))),
+            "url" => parse_url(value).map(Self::Url),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// An http or https URL. Anything else is refused, the URL is handed to curl.
+pub fn parse_url(s: &str) -> Result<String, String> {
+    let url = s.trim();
+    let lower = url.to_lowercase();
+    let rest = lower
+        .strip_prefix("http://")
+        .or_else(|| lower.strip_prefix("https://"));
+    if rest.is_none_or(|rest| rest.is_empty() || rest.contains(char::is_whitespace)) {
+        return Err(format!(
+            "invalid URL '{url}', it has to start with http:// or https://"
+        ));
+    }
+    Ok(url.to_string())
+}
+
+impl TryFrom<String> for Condition {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Condition> for String {
+    fn from(value: Condition) -> Self {
+        value.to_string()
+    }
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::After(time) => {
+                let time = time.format(&Rfc3339).map_err(|_| fmt::Error)?;
+                write!(f, "after {time}")
+            }
+            Self::File(path) => write!(f, "file {}", path.display()),
+            Self::Url(url) => write!(f, "url {url}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urls_need_an_http_scheme() {
+        let condition = "url https://example.com/status".parse::<Condition>();
+        assert_eq!(
+            condition,
+            Ok(Condition::Url("https://example.com/status".to_string()))
+        );
+        assert!("url HTTP://example.com".parse::<Condition>().is_ok());
+        for invalid in [
+            "url -o/tmp/x",
+            "url --config=/tmp/x",
+            "url file:///etc/passwd",
+            "url http://",
+            "url example.com",
+            "url https://a b",
+        ] {
+            assert!(invalid.parse::<Condition>().is_err(), "{invalid} parsed");
+        }
+    }
+
+    #[test]
+    fn text_round_trips() {
+        for text in [
+            "after 2025-07-01T00:00:00Z",
+            "file /tmp/done",
+            "url http://localhost:8080/health",
+        ] {
+            assert_eq!(text.parse::<Condition>().unwrap().to_string(), text);
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Display},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use time::{Duration, OffsetDateTime};
+
+use crate::{
+    parse,
+    store::{Priority, Task},
+};
+
+pub const DEFAULTS_FILE: &str = "defaults.json";
+
+/// Settings a task picks up when it gains a tag or project, e.g.
+/// `{"tags": {"urgent": {"priority": "high", "due": "1d", "remind": "2h"}}, "projects": {...}}`
+#[derive(Debug, Default)]
+pub struct Defaults {
+    pub tags: HashMap<String, Setting>,
+    pub projects: HashMap<String, Setting>,
+}
+
+#[derive(Debug, Default)]
+pub struct Setting {
+    pub priority: Option<Priority>,
+    /// Due this long after the task gained the tag or project
+    pub due: Option<Duration>,
+    /// Reminded of this long before it is due
+    pub remind: Option<Duration>,
+}
+
+#[derive(Deserialize)]
+struct RawDefaults {
+    #[serde(default)]
+    tags: HashMap<String, RawSetting>,
+    #[serde(default)]
+    projects: HashMap<String, RawSetting>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawSetting {
+    #[serde(default)]
+    priority: Option<String>,
+    #[serde(default)]
+    due: Option<String>,
+    #[serde(default)]
+    remind: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum DefaultsError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, serde_json::Error),
+    Invalid(PathBuf, String),
+}
+
+impl Display for DefaultsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(p, io_err) => write!(f, "I/O error on {p:?}: {io_err}"),
+            Self::Parse(p, json_err) => write!(f, "{p:?} is not a valid defaults file: {json_err}"),
+            Self::Invalid(p, message) => write!(f, "Invalid default in {p:?}: {message}"),
+        }
+    }
+}
+
+impl Error for DefaultsError {}
+
+/// Reads the defaults at `path`, a missing file means there are none
+pub fn load(path: &Path) -> Result<Defaults, DefaultsError> {
+    let json = match fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Defaults::default()),
+        Err(e) => return Err(DefaultsError::Io(path.to_path_buf(), e)),
+    };
+    let raw: RawDefaults =
+        serde_json::from_str(&json).map_err(|e| DefaultsError::Parse(path.to_path_buf(), e))?;
+
+    let invalid = |message: String| DefaultsError::Invalid(path.to_path_buf(), message);
+    let setting = |raw: RawSetting| -> Result<Setting, DefaultsError> {
+        Ok(Setting {
+            priority: raw
+                .priority
+                .as_deref()
+                .map(str::parse)
+                .transpose()
+                .map_err(invalid)?,
+            due: raw
+                .due
+                .as_deref()
+                .map(parse::parse_duration)
+                .transpose()
+                .map_err(invalid)?,
+            remind: raw
+                .remind
+                .as_deref()
+                .map(parse::parse_duration)
+                .transpose()
+                .map_err(invalid)?,
+        })
+    };
+
+    let mut defaults = Defaults::default();
+    for (tag, raw) in raw.tags {
+        let tag = parse::parse_tag(&tag).map_err(invalid)?;
+        defaults.tags.insert(tag, setting(raw)?);
+    }
+    for (project, raw) in raw.projects {
+        let project = parse::parse_project(&project).map_err(invalid)?;
+        defaults.projects.insert(project, setting(raw)?);
+    }
+    Ok(defaults)
+}
+
+impl Defaults {
+    /// Fills in the priority, due date and reminder of `task` from the defaults of the tags and project it
+    /// just gained, returning where values came from. Values the task already has are kept, so
+    /// anything set explicitly wins, and the project goes before the tags in their order.
+    pub fn apply(
+        &self,
+        task: &mut Task,
+        tags: &[String],
+        project: Option<&str>,
+        now: OffsetDateTime,
+    ) -> Vec<String> {
+        let project = project.and_then(|project| {
+            let setting = self.projects.get(project)?;
+            Some((format!("project {project}"), setting))
+        });
+        let tags = tags
+            .iter()
+            .filter_map(|tag| Some((format!("+{tag}"), self.tags.get(tag)?)));
+
+        let mut applied = Vec::new();
+        for (source, setting) in project.into_iter().chain(tags) {
+            let mut used = false;
+            if task.priority.is_none() && setting.priority.is_some() {
+                task.priority = setting.priority;
+                used = true;
+            }
+            if task.due.is_none()
+                && let Some(due) = setting.due.and_then(|due| now.checked_add(due))
+            {
+                task.due = Some(due);
+                used = true;
+            }
+            if task.remind.is_none() && setting.remind.is_some() {
+                task.remind = setting.remind;
+                used = true;
+            }
+            if used {
+                applied.push(source);
+            }
+        }
+        applied
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    fn load_str(name: &str, json: &str) -> Result<Defaults, DefaultsError> {
+        let dir = env::temp_dir().join(format!("taskr-defaults-{name}-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(DEFAULTS_FILE);
+        fs::write(&path, json).unwrap();
+        load(&path)
+    }
+
+    fn setting(priority: Option<Priority>, due: Option<Duration>) -> Setting {
+        Setting {
+            priority,
+            due,
+            remind: None,
+        }
+    }
+
+    #[test]
+    fn parses_tags_and_projects() {
+        let json = r#"{
+            "tags": {"+urgent": {"priority": "high", "due": "1d", "remind": "2h"}},
+            "projects": {" home ": {"priority": "low"}}
+        }"#;
+        let defaults = load_str("parse", json).unwrap();
+        let urgent = &defaults.tags["urgent"];
+        assert_eq!(urgent.priority, Some(Priority::High));
+        assert_eq!(urgent.due, Some(Duration::days(1)));
+        assert_eq!(urgent.remind, Some(Duration::hours(2)));
+        let home = &defaults.projects["home"];
+        assert_eq!((home.priority, home.due), (Some(Priority::Low), None));
+    }
+
+    #[test]
+    fn missing_file_has_no_defaults() {
+        let path = env::temp_dir().join(format!("taskr-defaults-missing-{}", process::id()));
+        let defaults = load(&path.join(DEFAULTS_FILE)).unwrap();
+        assert!(defaults.tags.is_empty() && defaults.projects.is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_settings() {
+        let invalid = [
+            r#"{"tags": {"work": {"priority": "urgent"}}}"#,
+            r#"{"tags": {"work": {"due": "soon"}}}"#,
+            r#"{"tags": {"two words": {"priority": "low"}}}"#,
+        ];
+        for json in invalid {
+            assert!(
+                matches!(load_str("invalid", json), Err(DefaultsError::Invalid(..))),
+                "{json} loaded"
+            );
+        }
+        let unknown = r#"{"tags": {"work": {"colour": "red"}}}"#;
+        assert!(matches!(
+            load_str("unknown", unknown),
+            Err(DefaultsError::Parse(..))
+        ));
+    }
+
+    #[test]
+    fn apply_keeps_explicit_values() {
+        let now = OffsetDateTime::UNIX_EPOCH;
+        let mut defaults = Defaults::default();
+        let phone = setting(Some(Priority::High), Some(Duration::days(1)));
+        defaults.tags.insert("phone".to_string(), phone);
+        let mut task = Task::new("call mom".to_string(), now);
+        task.priority = Some(Priority::Low);
+
+        let applied = defaults.apply(&mut task, &["phone".to_string()], None, now);
+        assert_eq!(applied, ["+phone"]);
+        assert_eq!(task.priority, Some(Priority::Low));
+        assert_eq!(task.due, Some(now + Duration::days(1)));
+    }
+
+    #[test]
+    fn apply_prefers_the_project_and_skips_unused_sources() {
+        let now = OffsetDateTime::UNIX_EPOCH;
+        let mut defaults = Defaults::default();
+        let work = setting(Some(Priority::Medium), None);
+        defaults.projects.insert("work".to_string(), work);
+        let urgent = setting(Some(Priority::Critical), None);
+        defaults.tags.insert("urgent".to_string(), urgent);
+        let tags = ["urgent".to_string(), "other".to_string()];
+        let mut task = Task::new("ship".to_string(), now);
+
+        let applied = defaults.apply(&mut task, &tags, Some("work"), now);
+        assert_eq!(applied, ["project work"]);
+        assert_eq!(task.priority, Some(Priority::Medium));
+
+        let mut untouched = Task::new("read".to_string(), now);
+        assert!(defaults.apply(&mut untouched, &[], None, now).is_empty());
+        assert_eq!(untouched.priority, None);
+    }
+}
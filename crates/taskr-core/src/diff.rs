@@ -0,0 +1,307 @@
+use serde::Serialize;
+use time::{Duration, OffsetDateTime};
+
+use crate::store::{Priority, Task, TaskStore};
+
+/// The tasks added, removed and changed between two versions of a store
+#[derive(Serialize)]
+pub struct StoreDiff<'a> {
+    pub added: Vec<DiffTask<'a>>,
+    pub removed: Vec<DiffTask<'a>>,
+    pub changed: Vec<ChangedTask>,
+}
+
+#[derive(Serialize)]
+pub struct DiffTask<'a> {
+    pub id: u64,
+    pub task: &'a Task,
+}
+
+#[derive(Serialize)]
+pub struct ChangedTask {
+    pub id: u64,
+    pub fields: Vec<FieldChange>,
+}
+
+/// A field as text before and after the change
+#[derive(Serialize)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+/// What changed between two versions of a store. Times are shown with `format_time`.
+pub fn diff<'a>(
+    old: &'a TaskStore,
+    new: &'a TaskStore,
+    format_time: impl Fn(OffsetDateTime) -> String,
+) -> StoreDiff<'a> {
+    let mut added = new
+        .tasks()
+        .iter()
+        .filter(|(id, _)| !old.tasks().contains_key(id))
+        .map(|(id, task)| DiffTask { id: *id, task })
+        .collect::<Vec<_>>();
+    added.sort_by_key(|t| t.id);
+
+    let mut removed = old
+        .tasks()
+        .iter()
+        .filter(|(id, _)| !new.tasks().contains_key(id))
+        .map(|(id, task)| DiffTask { id: *id, task })
+        .collect::<Vec<_>>();
+    removed.sort_by_key(|t| t.id);
+
+    let mut changed = Vec::new();
+    for (id, new_task) in new.tasks() {
+        let Some(old_task) = old.get(*id) else {
+            continue;
+        };
+
+        let mut fields = Vec::new();
+        if old_task.description != new_task.description {
+            fields.push(FieldChange {
+                field: "description",
+                old: old_task.description.clone(),
+                new: new_task.description.clone(),
+            });
+        }
+        if old_task.status != new_task.status {
+            fields.push(FieldChange {
+                field: "status",
+                old: old_task.status.to_string(),
+                new: new_task.status.to_string(),
+            });
+        }
+        if old_task.project != new_task.project {
+            fields.push(FieldChange {
+                field: "project",
+                old: old_task.project.clone().unwrap_or_default(),
+                new: new_task.project.clone().unwrap_or_default(),
+            });
+        }
+        if old_task.parent != new_task.parent {
+            let parent = |task: &Task| task.parent.map(|id| format!("#{id}")).unwrap_or_default();
+            fields.push(FieldChange {
+                field: "parent",
+                old: parent(old_task),
+                new: parent(new_task),
+            });
+        }
+        if old_task.depends_on != new_task.depends_on {
+            let depends_on = |task: &Task| {
+                task.depends_on
+                    .iter()
+                    .map(|id| format!("#{id}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            fields.push(FieldChange {
+                field: "depends_on",
+                old: depends_on(old_task),
+                new: depends_on(new_task),
+            });
+        }
+        if old_task.repeat != new_task.repeat {
+            let repeat = |task: &Task| task.repeat.map(|r| r.to_string()).unwrap_or_default();
+            fields.push(FieldChange {
+                field: "repeat",
+                old: repeat(old_task),
+                new: repeat(new_task),
+            });
+        }
+        if old_task.tags != new_task.tags {
+            fields.push(FieldChange {
+                field: "tags",
+                old: old_task.tags.join(", "),
+                new: new_task.tags.join(", "),
+            });
+        }
+        if old_task.priority != new_task.priority {
+            let format_priority =
+                |priority: Option<Priority>| priority.map(|p| p.to_string()).unwrap_or_default();
+            fields.push(FieldChange {
+                field: "priority",
+                old: format_priority(old_task.priority),
+                new: format_priority(new_task.priority),
+            });
+        }
+        if old_task.due != new_task.due {
+            let format_due =
+                |due: Option<OffsetDateTime>| due.map(&format_time).unwrap_or_default();
+            fields.push(FieldChange {
+                field: "due",
+                old: format_due(old_task.due),
+                new: format_due(new_task.due),
+            });
+        }
+        if old_task.remind != new_task.remind {
+            let remind = |task: &Task| task.remind.map(format_duration).unwrap_or_default();
+            fields.push(FieldChange {
+                field: "remind",
+                old: remind(old_task),
+                new: remind(new_task),
+            });
+        }
+        if old_task.rank != new_task.rank {
+            let rank = |task: &Task| task.rank.map(|rank| rank.to_string()).unwrap_or_default();
+            fields.push(FieldChange {
+                field: "rank",
+                old: rank(old_task),
+                new: rank(new_task),
+            });
+        }
+        if old_task.waiting_on != new_task.waiting_on {
+            let waiting_on = |task: &Task| {
+                task.waiting_on
+                    .iter()
+                    .map(|condition| condition.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            fields.push(FieldChange {
+                field: "waiting_on",
+                old: waiting_on(old_task),
+                new: waiting_on(new_task),
+            });
+        }
+        if old_task.notes != new_task.notes {
+            let notes = |task: &Task| {
+                task.notes
+                    .iter()
+                    .map(|note| note.text.as_str())
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            };
+            fields.push(FieldChange {
+                field: "notes",
+                old: notes(old_task),
+                new: notes(new_task),
+            });
+        }
+        if old_task.session != new_task.session {
+            fields.push(FieldChange {
+                field: "session",
+                old: old_task.session.clone().unwrap_or_default(),
+                new: new_task.session.clone().unwrap_or_default(),
+            });
+        }
+        if old_task.expires != new_task.expires {
+            let expires = |task: &Task| task.expires.map(&format_time).unwrap_or_default();
+            fields.push(FieldChange {
+                field: "expires",
+                old: expires(old_task),
+                new: expires(new_task),
+            });
+        }
+        if old_task.reviewed != new_task.reviewed {
+            let reviewed = |task: &Task| task.reviewed.map(&format_time).unwrap_or_default();
+            fields.push(FieldChange {
+                field: "reviewed",
+                old: reviewed(old_task),
+                new: reviewed(new_task),
+            });
+        }
+        if old_task.uuid != new_task.uuid {
+            fields.push(FieldChange {
+                field: "uuid",
+                old: old_task.uuid.clone().unwrap_or_default(),
+                new: new_task.uuid.clone().unwrap_or_default(),
+            });
+        }
+        if old_task.created != new_task.created {
+            fields.push(FieldChange {
+                field: "created",
+                old: format_time(old_task.created),
+                new: format_time(new_task.created),
+            });
+        }
+        // A bare timestamp bump is only interesting if nothing else changed
+        if fields.is_empty() && old_task.updated != new_task.updated {
+            fields.push(FieldChange {
+                field: "updated",
+                old: format_time(old_task.updated),
+                new: format_time(new_task.updated),
+            });
+        }
+
+        if !fields.is_empty() {
+            changed.push(ChangedTask { id: *id, fields });
+        }
+    }
+    changed.sort_by_key(|t| t.id);
+
+    StoreDiff {
+        added,
+        removed,
+        changed,
+    }
+}
+
+/// In the largest unit that divides it evenly, the way durations are given, e.g. `2h`
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.whole_seconds();
+    let units = [
+        (Duration::WEEK, "w"),
+        (Duration::DAY, "d"),
+        (Duration::HOUR, "h"),
+    ];
+    for (unit, suffix) in units {
+        let unit = unit.whole_seconds();
+        if seconds != 0 && seconds % unit == 0 {
+            return format!("{}{suffix}", seconds / unit);
+        }
+    }
+    format!("{}m", duration.whole_minutes())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+    use crate::{condition::Condition, store::Note};
+
+    #[test]
+    fn every_field_is_compared() {
+        let now = OffsetDateTime::UNIX_EPOCH;
+        let dir = env::temp_dir().join(format!("taskr-diff-{}", process::id()));
+        let mut old = TaskStore::open(dir.join("old.json")).unwrap();
+        let id = old.add("task".to_string(), now);
+        let mut new = TaskStore::open(dir.join("new.json")).unwrap();
+        let mut task = old.get(id).unwrap().clone();
+        task.notes.push(Note {
+            time: now,
+            text: "called".to_string(),
+        });
+        task.waiting_on
+            .push("file /tmp/ready".parse::<Condition>().unwrap());
+        task.remind = Some(Duration::hours(2));
+        task.uuid = Some("uuid".to_string());
+        new.restore(id, task);
+
+        let diff = diff(&old, &new, |time| time.to_string());
+        let fields = diff.changed[0]
+            .fields
+            .iter()
+            .map(|change| (change.field, change.new.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            [
+                ("remind", "2h"),
+                ("waiting_on", "file /tmp/ready"),
+                ("notes", "called"),
+                ("uuid", "uuid"),
+            ]
+        );
+    }
+
+    #[test]
+    fn durations_use_the_largest_even_unit() {
+        assert_eq!(format_duration(Duration::weeks(2)), "2w");
+        assert_eq!(format_duration(Duration::hours(36)), "36h");
+        assert_eq!(format_duration(Duration::minutes(90)), "90m");
+    }
+}
@@ -0,0 +1,191 @@
+use time::{Duration, OffsetDateTime};
+
+use crate::store::{Task, TaskStatus, TaskStore};
+
+/// Most tasks one run generates, more only makes for a store too slow to be useful
+pub const MAX_TASKS: u64 = 100_000;
+
+const VERBS: &[&str] = &[
+    "Fix", "Write", "Review", "Refactor", "Update", "Plan", "Call", "Email", "Buy", "Clean",
+    "Schedule", "Research", "Draft", "Test", "Deploy", "Read", "Book", "Organise",
+];
+
+const SUBJECTS: &[&str] = &[
+    "the login page",
+    "quarterly report",
+    "groceries",
+    "dentist appointment",
+    "release notes",
+    "backup script",
+    "team meeting",
+    "tax documents",
+    "garden shed",
+    "blog post",
+    "CI pipeline",
+    "flight to Berlin",
+    "birthday present",
+    "database migration",
+    "onboarding docs",
+    "bike repairs",
+];
+
+const SUFFIXES: &[&str] = &[
+    "",
+    "",
+    "",
+    " before Friday",
+    " with Sam",
+    " (again)",
+    " for the client",
+    " this weekend",
+    " and follow up",
+];
+
+const OTHER_STATUSES: &[&str] = &["waiting", "blocked"];
+
+const TAGS: &[&str] = &[
+    "work", "home", "errand", "urgent", "health", "finance", "writing", "ops",
+];
+
+/// The number of tasks to generate, from 1 up to `MAX_TASKS`
+pub fn parse_count(s: &str) -> Result<u64, String> {
+    let count = s
+        .parse::<u64>()
+        .map_err(|_| format!("'{s}' is not a number of tasks"))?;
+    if !(1..=MAX_TASKS).contains(&count) {
+        return Err(format!("Generate between 1 and {MAX_TASKS} tasks"));
+    }
+    Ok(count)
+}
+
+/// Adds `count` random tasks to the store, the same seed always giving the same tasks. They are
+/// spread over the year before `now`, with updates somewhere between creation and `now`.
+pub fn populate(store: &mut TaskStore, count: u64, seed: u64, now: OffsetDateTime) -> Vec<u64> {
+    let mut rng = Rng::new(seed);
+    let year_secs = Duration::days(365).whole_seconds() as u64;
+    let mut generated = Vec::new();
+    for _ in 0..count {
+        let created = now - Duration::seconds(rng.below(year_secs) as i64);
+        let since_created = (now - created).whole_seconds() as u64;
+        let updated = created + Duration::seconds(rng.below(since_created + 1) as i64);
+
+        let mut task = Task::new(description(&mut rng), created);
+        task.status = status(&mut rng);
+        task.updated = updated;
+        task.due = due(&mut rng, created);
+        task.tags = tags(&mut rng);
+        task.depends_on = depends_on(&mut rng, &generated);
+        generated.push(store.insert(task));
+    }
+    generated
+}
+
+/// Small deterministic PRNG (SplitMix64) so the same seed always yields the same store
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in `0..upper`, `upper` must be non-zero
+    pub fn below(&mut self, upper: u64) -> u64 {
+        self.next_u64() % upper
+    }
+
+    pub fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len() as u64) as usize]
+    }
+}
+
+pub fn description(rng: &mut Rng) -> String {
+    format!(
+        "{} {}{}",
+        rng.pick(VERBS),
+        rng.pick(SUBJECTS),
+        rng.pick(SUFFIXES)
+    )
+}
+
+/// Roughly half todo, a third complete and the rest in progress or in custom statuses
+pub fn status(rng: &mut Rng) -> TaskStatus {
+    match rng.below(100) {
+        0..50 => TaskStatus::Todo,
+        50..85 => TaskStatus::Complete,
+        85..93 => TaskStatus::InProgress,
+        _ => TaskStatus::Other(rng.pick(OTHER_STATUSES).to_string()),
+    }
+}
+
+/// Due on a day up to three months after creation for roughly 40% of tasks
+pub fn due(rng: &mut Rng, created: OffsetDateTime) -> Option<OffsetDateTime> {
+    if rng.below(100) >= 40 {
+        return None;
+    }
+    Some(created + Duration::days(rng.below(91) as i64))
+}
+
+/// Up to two distinct tags, most tasks have at least one
+pub fn tags(rng: &mut Rng) -> Vec<String> {
+    let mut tags = Vec::new();
+    for _ in 0..rng.below(3) {
+        let tag = rng.pick(TAGS).to_string();
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// Up to two of the `earlier` tasks for roughly a fifth of tasks. Only depending on tasks
+/// generated before keeps the dependencies free of cycles.
+pub fn depends_on(rng: &mut Rng, earlier: &[u64]) -> Vec<u64> {
+    if earlier.is_empty() || rng.below(100) >= 20 {
+        return Vec::new();
+    }
+    let mut ids = Vec::new();
+    for _ in 0..=rng.below(2) {
+        let id = earlier[rng.below(earlier.len() as u64) as usize];
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_store() {
+        let now = OffsetDateTime::UNIX_EPOCH + Duration::days(1000);
+        let generate = |seed| {
+            let path = std::env::temp_dir().join(format!("taskr-generate-{}", std::process::id()));
+            let mut store = TaskStore::open(path.join("tasks.json")).unwrap();
+            populate(&mut store, 200, seed, now);
+            store.tasks().clone()
+        };
+        assert_eq!(generate(42), generate(42));
+        assert_ne!(generate(42), generate(43));
+    }
+
+    #[test]
+    fn count_is_bounded() {
+        assert_eq!(parse_count("100"), Ok(100));
+        assert_eq!(parse_count(&MAX_TASKS.to_string()), Ok(MAX_TASKS));
+        assert!(parse_count("0").is_err());
+        assert!(parse_count(&(MAX_TASKS + 1).to_string()).is_err());
+        assert!(parse_count("many").is_err());
+    }
+}
@@ -0,0 +1,56 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::persist;
+
+/// FNV-1a, stable across Rust versions unlike `DefaultHasher`
+pub fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Sidecar file holding the checksum, e.g. tasks.json -> tasks.json.sum
+pub fn sidecar_path(path: &Path) -> PathBuf {
+    let mut sidecar = path.as_os_str().to_owned();
+    sidecar.push(".sum");
+    PathBuf::from(sidecar)
+}
+
+pub fn write_checksum(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let checksum = format!("{:016x}", checksum(contents));
+    persist::write_atomic(&sidecar_path(path), checksum.as_bytes())
+}
+
+/// Adds the checksum of `contents` to the sidecar before the file is replaced with them. The
+/// current file keeps its checksum if it had a valid one, so a crash before either write
+/// finishes leaves whichever version is on disk verified. `write_checksum` afterwards drops the
+/// old one.
+pub fn expect_checksum(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut checksums = format!("{:016x}", checksum(contents));
+    if let Ok(current) = fs::read(path)
+        && verify(path, &current)
+    {
+        checksums.push_str(&format!("\n{:016x}", checksum(&current)));
+    }
+    persist::write_atomic(&sidecar_path(path), checksums.as_bytes())
+}
+
+/// Returns `false` when a sidecar exists and does not match `contents`. A missing or unreadable
+/// sidecar counts as valid so stores written by older versions still load.
+pub fn verify(path: &Path, contents: &[u8]) -> bool {
+    matches(&sidecar_path(path), contents).unwrap_or(true)
+}
+
+/// Whether a checksum in `sidecar` matches `contents`, `None` when it cannot be read
+pub fn matches(sidecar: &Path, contents: &[u8]) -> Option<bool> {
+    let stored = fs::read_to_string(sidecar).ok()?;
+    let checksum = checksum(contents);
+    Some(
+        stored.lines().any(|line| {
+            u64::from_str_radix(line.trim(), 16).is_ok_and(|stored| stored == checksum)
+        }),
+    )
+}
@@ -0,0 +1,130 @@
+use std::{
+    collections::BTreeSet,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::store::Task;
+
+/// One task before and after an operation, `None` where it did not exist
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Change {
+    pub id: u64,
+    pub before: Option<Task>,
+    pub after: Option<Task>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ProjectsChange {
+    pub before: BTreeSet<String>,
+    pub after: BTreeSet<String>,
+}
+
+/// A saved mutation, holding enough state to revert and reapply it
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Operation {
+    pub seq: u64,
+    #[serde(with = "time::serde::rfc3339")]
+    pub time: OffsetDateTime,
+    /// The command that made the change, for display
+    pub label: String,
+    pub changes: Vec<Change>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub projects: Option<ProjectsChange>,
+    /// Tasks that moved between the task list and the archive, undo and redo move them back
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub archived: Vec<u64>,
+}
+
+/// The journal is append-only, undoing and redoing are recorded as entries of their own
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Entry {
+    Op(Operation),
+    Undo {
+        seq: u64,
+        #[serde(with = "time::serde::rfc3339")]
+        time: OffsetDateTime,
+    },
+    Redo {
+        seq: u64,
+        #[serde(with = "time::serde::rfc3339")]
+        time: OffsetDateTime,
+    },
+}
+
+/// Journal kept next to the store, e.g. tasks.json -> tasks.json.journal
+pub fn journal_path(path: &Path) -> PathBuf {
+    let mut journal = path.as_os_str().to_owned();
+    journal.push(".journal");
+    PathBuf::from(journal)
+}
+
+/// Appends entries as JSON lines in a single write
+pub fn append(path: &Path, entries: &[Entry]) -> io::Result<()> {
+    let mut lines = String::new();
+    for entry in entries {
+        lines += &serde_json::to_string(entry).expect("Failed to serialize journal entry");
+        lines.push('\n');
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(lines.as_bytes())
+}
+
+/// Entries oldest first. A missing journal is an empty one and lines that do not parse, e.g. from
+/// an interrupted write, are skipped.
+pub fn read(path: &Path) -> io::Result<Vec<Entry>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(contents
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Replays the journal into the operations that can be undone and those that can be redone,
+/// each with the next candidate last
+pub fn stacks(entries: &[Entry]) -> (Vec<&Operation>, Vec<&Operation>) {
+    let mut done: Vec<&Operation> = Vec::new();
+    let mut undone: Vec<&Operation> = Vec::new();
+    for entry in entries {
+        match entry {
+            Entry::Op(op) => {
+                done.push(op);
+                // A new change starts a new branch, what was undone before can't come back
+                undone.clear();
+            }
+            Entry::Undo { seq, .. } => {
+                if done.last().is_some_and(|op| op.seq == *seq) {
+                    undone.extend(done.pop());
+                }
+            }
+            Entry::Redo { seq, .. } => {
+                if undone.last().is_some_and(|op| op.seq == *seq) {
+                    done.extend(undone.pop());
+                }
+            }
+        }
+    }
+    (done, undone)
+}
+
+/// Sequence number for the next operation
+pub fn next_seq(entries: &[Entry]) -> u64 {
+    entries
+        .iter()
+        .filter_map(|entry| match entry {
+            Entry::Op(op) => Some(op.seq),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0)
+        + 1
+}
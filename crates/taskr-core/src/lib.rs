@@ -0,0 +1,17 @@
+//! The task model, its storage and filtering, shared by every frontend
+
+pub mod condition;
+pub mod defaults;
+pub mod diff;
+pub mod generate;
+pub mod integrity;
+pub mod journal;
+pub mod parse;
+pub mod pattern;
+pub mod persist;
+pub mod profile;
+pub mod query;
+pub mod recurrence;
+pub mod references;
+pub mod rules;
+pub mod store;
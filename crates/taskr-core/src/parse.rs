@@ -0,0 +1,171 @@
+use std::ops::RangeInclusive;
+
+use time::{Date, Duration, OffsetDateTime, PrimitiveDateTime, Time, format_description};
+
+const SINCE_EXAMPLES: &str = "today, yesterday, 2024-12-01, 2024-12-01T17:00 or 3d";
+const DUE_EXAMPLES: &str = "today, tomorrow, 2024-12-01, 2024-12-01T17:00 or 3d";
+const DURATION_EXAMPLES: &str = "30m, 12h, 3d or 2w";
+const MAX_RANGE_LEN: u64 = 10_000;
+
+pub fn now() -> OffsetDateTime {
+    OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc())
+}
+
+/// Parses a point in the past for `--since` style arguments. Days resolve to their start,
+/// durations count back from now.
+pub fn parse_since(s: &str) -> Result<OffsetDateTime, String> {
+    let now = now();
+    let s = s.trim();
+
+    match s.to_lowercase().as_str() {
+        "today" => return Ok(start_of_day(now, now.date())),
+        "yesterday" => return Ok(start_of_day(now, now.date() - Duration::days(1))),
+        _ => {}
+    }
+
+    if let Ok(duration) = parse_duration(s) {
+        return now
+            .checked_sub(duration)
+            .ok_or_else(|| format!("'{s}' reaches back too far"));
+    }
+    if let Some(date_time) = parse_date_time(s, now) {
+        return Ok(date_time);
+    }
+    parse_date(s)
+        .map(|date| start_of_day(now, date))
+        .ok_or_else(|| format!("invalid date '{s}', try {SINCE_EXAMPLES}"))
+}
+
+/// Parses a due date. Days are due by the end of the day, durations count forward from now.
+pub fn parse_due(s: &str) -> Result<OffsetDateTime, String> {
+    let now = now();
+    let s = s.trim();
+
+    match s.to_lowercase().as_str() {
+        "today" => return Ok(end_of_day(now, now.date())),
+        "tomorrow" => return Ok(end_of_day(now, now.date() + Duration::days(1))),
+        _ => {}
+    }
+
+    if let Ok(duration) = parse_duration(s) {
+        return now
+            .checked_add(duration)
+            .ok_or_else(|| format!("'{s}' reaches too far into the future"));
+    }
+    if let Some(date_time) = parse_date_time(s, now) {
+        return Ok(date_time);
+    }
+    parse_date(s)
+        .map(|date| end_of_day(now, date))
+        .ok_or_else(|| format!("invalid due date '{s}', try {DUE_EXAMPLES}"))
+}
+
+/// Parses a tag, a leading `+` as in `+work` is accepted and dropped
+pub fn parse_tag(s: &str) -> Result<String, String> {
+    let tag = s.trim().trim_start_matches('+');
+    if tag.is_empty() || tag.contains(char::is_whitespace) {
+        return Err(format!(
+            "invalid tag '{s}', tags are single words like work or +home"
+        ));
+    }
+    Ok(tag.to_string())
+}
+
+pub fn parse_project(s: &str) -> Result<String, String> {
+    let project = s.trim();
+    if project.is_empty() {
+        return Err("project name cannot be empty".to_string());
+    }
+    Ok(project.to_string())
+}
+
+/// Profile names become directory names, so they are limited to letters, digits, `-` and `_`
+pub fn parse_profile(s: &str) -> Result<String, String> {
+    let profile = s.trim();
+    let allowed = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    if profile.is_empty() || !profile.chars().all(allowed) {
+        return Err(format!(
+            "invalid profile '{s}', use letters, digits, '-' and '_' like work or home"
+        ));
+    }
+    Ok(profile.to_string())
+}
+
+/// Parses a task ID or an inclusive range of them such as `7-12`
+pub fn parse_id_range(s: &str) -> Result<RangeInclusive<u64>, String> {
+    let invalid = || format!("invalid ID '{s}', try 7 or a range like 7-12");
+
+    let s = s.trim();
+    let (start, end) = s.split_once('-').unwrap_or((s, s));
+    let start = start.trim().parse::<u64>().map_err(|_| invalid())?;
+    let end = end.trim().parse::<u64>().map_err(|_| invalid())?;
+    if start > end {
+        return Err(format!(
+            "invalid range '{s}', the first ID has to be the lower one"
+        ));
+    }
+    if end - start >= MAX_RANGE_LEN {
+        return Err(format!(
+            "range '{s}' is too large, ranges can span up to {MAX_RANGE_LEN} IDs"
+        ));
+    }
+    Ok(start..=end)
+}
+
+/// Parses a template variable given as `name=value`
+pub fn parse_var(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.to_string()))
+        }
+        _ => Err(format!("invalid variable '{s}', try component=ui")),
+    }
+}
+
+/// Parses durations such as `3d`, made of a number and a unit (m, h, d, w)
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let invalid = || format!("invalid duration '{s}', try {DURATION_EXAMPLES}");
+
+    let split = s.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let (amount, unit) = s.split_at(split);
+    let amount = amount.parse::<i64>().map_err(|_| invalid())?;
+
+    let unit = match unit {
+        "m" | "min" | "mins" => Duration::MINUTE,
+        "h" | "hour" | "hours" => Duration::HOUR,
+        "d" | "day" | "days" => Duration::DAY,
+        "w" | "week" | "weeks" => Duration::WEEK,
+        _ => return Err(invalid()),
+    };
+    // The constructors such as `Duration::weeks` panic on overflow
+    amount
+        .checked_mul(unit.whole_seconds())
+        .map(Duration::seconds)
+        .ok_or_else(invalid)
+}
+
+/// `YYYY-MM-DDTHH:MM`, interpreted in the offset of `now`
+fn parse_date_time(s: &str, now: OffsetDateTime) -> Option<OffsetDateTime> {
+    let format = format_description::parse("[year]-[month]-[day]T[hour]:[minute]")
+        .expect("Failed parse format");
+
+    PrimitiveDateTime::parse(s, &format)
+        .ok()
+        .map(|date_time| date_time.assume_offset(now.offset()))
+}
+
+/// `YYYY-MM-DD`
+fn parse_date(s: &str) -> Option<Date> {
+    let format = format_description::parse("[year]-[month]-[day]").expect("Failed parse format");
+    Date::parse(s, &format).ok()
+}
+
+fn start_of_day(now: OffsetDateTime, date: Date) -> OffsetDateTime {
+    PrimitiveDateTime::new(date, Time::MIDNIGHT).assume_offset(now.offset())
+}
+
+fn end_of_day(now: OffsetDateTime, date: Date) -> OffsetDateTime {
+    let last_second = Time::from_hms(23, 59, 59).expect("Valid time");
+    PrimitiveDateTime::new(date, last_second).assume_offset(now.offset())
+}
@@ -0,0 +1,536 @@
+use std::{iter::Peekable, ops::Range, str::Chars};
+
+/// Groups nested deeper than this are refused, parsing and compiling recurse into each one
+const MAX_NESTING: usize = 50;
+/// Patterns that compile to more instructions than this are refused. Counted repetitions are
+/// written out, so `(a{1000}){1000}` would otherwise take a million.
+const MAX_INSTRUCTIONS: usize = 10_000;
+
+/// A small regular expression matcher for searching task text. It supports literals, `.`,
+/// classes like `[a-z]` and `[^0-9]`, `\d` `\w` `\s` and their negations, `^`, `$`, groups,
+/// `|` and the `*` `+` `?` `{n,m}` quantifiers. Every way the pattern can match is followed in
+/// lockstep instead of backtracking, so a search takes time proportional to the text length
+/// times the pattern size, however the pattern is written.
+#[derive(Debug)]
+pub struct Pattern {
+    program: Vec<Inst>,
+    ignore_case: bool,
+}
+
+#[derive(Clone, Debug)]
+enum Node {
+    Char(char),
+    Any,
+    Class {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    Start,
+    End,
+    Group(Vec<Vec<Node>>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+    },
+}
+
+impl Pattern {
+    pub fn new(pattern: &str, ignore_case: bool) -> Result<Self, String> {
+        let mut chars = pattern.chars().peekable();
+        let alternatives = parse_alternatives(&mut chars, 0)?;
+        if chars.next().is_some() {
+            return Err("unmatched ')'".to_string());
+        }
+        let mut program = Vec::new();
+        compile(Node::Group(alternatives), &mut program)?;
+        if program.len() > MAX_INSTRUCTIONS {
+            return Err("pattern is too complex, try fewer or smaller repetitions".to_string());
+        }
+        program.push(Inst::Match);
+        Ok(Self {
+            program,
+            ignore_case,
+        })
+    }
+
+    /// Matches `text` as is, without any special characters
+    pub fn literal(text: &str, ignore_case: bool) -> Self {
+        let mut program = text.chars().map(Inst::Char).collect::<Vec<_>>();
+        program.push(Inst::Match);
+        Self {
+            program,
+            ignore_case,
+        }
+    }
+
+    /// Byte range of the leftmost match in `text`, preferring what a backtracking matcher would
+    /// find: the first alternative that works and as many repetitions as possible
+    pub fn find(&self, text: &str) -> Option<Range<usize>> {
+        let chars = text.char_indices().collect::<Vec<_>>();
+        let byte = |i: usize| chars.get(i).map_or(text.len(), |(byte, _)| *byte);
+        // Threads in priority order as (instruction, where their match started)
+        let mut current = Vec::new();
+        let mut next = Vec::new();
+        let mut added = vec![usize::MAX; self.program.len()];
+        let mut found = None;
+        for i in 0..=chars.len() {
+            // A match starting further left always wins, so only look for new ones until then
+            if found.is_none() {
+                self.add_thread(&mut current, &mut added, chars.len(), 0, i, i);
+            }
+            if current.is_empty() {
+                break;
+            }
+            for &(pc, start) in &current {
+                match &self.program[pc] {
+                    // Threads after this one have lower priority
+                    Inst::Match => {
+                        found = Some(start..i);
+                        break;
+                    }
+                    inst => {
+                        if chars.get(i).is_some_and(|(_, c)| self.accepts(inst, *c)) {
+                            self.add_thread(
+                                &mut next,
+                                &mut added,
+                                chars.len(),
+                                pc + 1,
+                                i + 1,
+                                start,
+                            );
+                        }
+                    }
+                }
+            }
+            current.clear();
+            std::mem::swap(&mut current, &mut next);
+        }
+        found.map(|range| byte(range.start)..byte(range.end))
+    }
+
+    /// Adds the thread at `pc` to `threads` at text position `i`, following jumps, splits and
+    /// anchors up to the instructions that consume a character
+    fn add_thread(
+        &self,
+        threads: &mut Vec<(usize, usize)>,
+        added: &mut [usize],
+        len: usize,
+        pc: usize,
+        i: usize,
+        start: usize,
+    ) {
+        // An explicit stack, long patterns would overflow the call stack. The preferred branch
+        // goes on top so threads come out in priority order.
+        let mut pending = vec![pc];
+        while let Some(pc) = pending.pop() {
+            // Each instruction runs once per position, which keeps empty loops like (a*)* finite
+            if added[pc] == i {
+                continue;
+            }
+            added[pc] = i;
+            match self.program[pc] {
+                Inst::Jump(to) => pending.push(to),
+                Inst::Split(preferred, other) => pending.extend([other, preferred]),
+                Inst::Start if i == 0 => pending.push(pc + 1),
+                Inst::End if i == len => pending.push(pc + 1),
+                Inst::Start | Inst::End => {}
+                _ => threads.push((pc, start)),
+            }
+        }
+    }
+
+    fn accepts(&self, inst: &Inst, c: char) -> bool {
+        match inst {
+            Inst::Char(expected) => self.variants(c).contains(expected),
+            Inst::Any => c != '\n',
+            Inst::Class { ranges, negated } => {
+                let inside = self
+                    .variants(c)
+                    .iter()
+                    .any(|c| ranges.iter().any(|(low, high)| (*low..=*high).contains(c)));
+                inside != *negated
+            }
+            _ => unreachable!("only single characters are accepted one at a time"),
+        }
+    }
+
+    /// The character itself and, when ignoring case, its other cases
+    fn variants(&self, c: char) -> [char; 3] {
+        if !self.ignore_case {
+            return [c; 3];
+        }
+        // Cases that map to several characters, e.g. ß, are left as they are
+        let single = |case: &mut dyn Iterator<Item = char>| match (case.next(), case.next()) {
+            (Some(case), None) => case,
+            _ => c,
+        };
+        [
+            c,
+            single(&mut c.to_lowercase()),
+            single(&mut c.to_uppercase()),
+        ]
+    }
+}
+
+/// Instructions that `find` steps every thread through in lockstep
+#[derive(Debug)]
+enum Inst {
+    Char(char),
+    Any,
+    Class {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    Start,
+    End,
+    /// Continues at both, preferring the first
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+/// Translates the parsed nodes into instructions, counted repetitions are written out in full
+fn compile(node: Node, program: &mut Vec<Inst>) -> Result<(), String> {
+    if program.len() > MAX_INSTRUCTIONS {
+        return Err("pattern is too complex, try fewer or smaller repetitions".to_string());
+    }
+    match node {
+        Node::Char(c) => program.push(Inst::Char(c)),
+        Node::Any => program.push(Inst::Any),
+        Node::Class { ranges, negated } => program.push(Inst::Class { ranges, negated }),
+        Node::Start => program.push(Inst::Start),
+        Node::End => program.push(Inst::End),
+        Node::Group(alternatives) => {
+            let last = alternatives.len() - 1;
+            let mut jumps = Vec::new();
+            for (index, sequence) in alternatives.into_iter().enumerate() {
+                let split = program.len();
+                if index < last {
+                    program.push(Inst::Split(split + 1, 0));
+                }
+                for node in sequence {
+                    compile(node, program)?;
+                }
+                if index < last {
+                    jumps.push(program.len());
+                    program.push(Inst::Jump(0));
+                    program[split] = Inst::Split(split + 1, program.len());
+                }
+            }
+            for jump in jumps {
+                program[jump] = Inst::Jump(program.len());
+            }
+        }
+        Node::Repeat { node, min, max } => {
+            for _ in 0..min {
+                compile(node.as_ref().clone(), program)?;
+            }
+            match max {
+                None => {
+                    let split = program.len();
+                    program.push(Inst::Split(split + 1, 0));
+                    compile(*node, program)?;
+                    program.push(Inst::Jump(split));
+                    program[split] = Inst::Split(split + 1, program.len());
+                }
+                Some(max) => {
+                    let mut splits = Vec::new();
+                    for _ in min..max {
+                        splits.push(program.len());
+                        program.push(Inst::Split(program.len() + 1, 0));
+                        compile(node.as_ref().clone(), program)?;
+                    }
+                    for split in splits {
+                        program[split] = Inst::Split(split + 1, program.len());
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_alternatives(
+    chars: &mut Peekable<Chars>,
+    nesting: usize,
+) -> Result<Vec<Vec<Node>>, String> {
+    let mut alternatives = vec![parse_sequence(chars, nesting)?];
+    while chars.next_if_eq(&'|').is_some() {
+        alternatives.push(parse_sequence(chars, nesting)?);
+    }
+    Ok(alternatives)
+}
+
+fn parse_sequence(chars: &mut Peekable<Chars>, nesting: usize) -> Result<Vec<Node>, String> {
+    let mut sequence = Vec::new();
+    while let Some(&c) = chars.peek() {
+        if c == '|' || c == ')' {
+            break;
+        }
+        chars.next();
+        let node = match c {
+            '(' => {
+                if nesting == MAX_NESTING {
+                    return Err(format!("groups nested deeper than {MAX_NESTING}"));
+                }
+                let alternatives = parse_alternatives(chars, nesting + 1)?;
+                if chars.next() != Some(')') {
+                    return Err("unclosed '('".to_string());
+                }
+                Node::Group(alternatives)
+            }
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '[' => parse_class(chars)?,
+            '\\' => parse_escape(chars)?,
+            '*' | '+' | '?' | '{' => return Err(format!("nothing to repeat before '{c}'")),
+            c => Node::Char(c),
+        };
+
+        let (min, max) = match chars.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                chars.next();
+                let bounds = parse_bounds(chars)?;
+                sequence.push(repeat(node, bounds)?);
+                continue;
+            }
+            _ => {
+                sequence.push(node);
+                continue;
+            }
+        };
+        chars.next();
+        sequence.push(repeat(node, (min, max))?);
+    }
+    Ok(sequence)
+}
+
+fn repeat(node: Node, (min, max): (usize, Option<usize>)) -> Result<Node, String> {
+    if matches!(node, Node::Start | Node::End) {
+        return Err("'^' and '$' cannot be repeated".to_string());
+    }
+    Ok(Node::Repeat {
+        node: Box::new(node),
+        min,
+        max,
+    })
+}
+
+/// `{n}`, `{n,}` or `{n,m}`, after the opening brace
+fn parse_bounds(chars: &mut Peekable<Chars>) -> Result<(usize, Option<usize>), String> {
+    let mut inside = String::new();
+    loop {
+        match chars.next() {
+            Some('}') => break,
+            Some(c) => inside.push(c),
+            None => return Err("unclosed '{'".to_string()),
+        }
+    }
+
+    let invalid = || format!("invalid repetition '{{{inside}}}'");
+    let number = |s: &str| s.trim().parse::<usize>().map_err(|_| invalid());
+    let (min, max) = match inside.split_once(',') {
+        None => {
+            let n = number(&inside)?;
+            (n, Some(n))
+        }
+        Some((min, "")) => (number(min)?, None),
+        Some((min, max)) => (number(min)?, Some(number(max)?)),
+    };
+    if max.is_some_and(|max| max < min) {
+        return Err(invalid());
+    }
+    Ok((min, max))
+}
+
+/// A bracketed class after the opening bracket, e.g. `a-z0-9_]`
+fn parse_class(chars: &mut Peekable<Chars>) -> Result<Node, String> {
+    let negated = chars.next_if_eq(&'^').is_some();
+    let mut ranges = Vec::new();
+    // A leading ] is a literal one rather than the end of an empty class
+    let mut first = true;
+    loop {
+        let c = match chars.next() {
+            Some(']') if !first => break,
+            Some('\\') => match parse_escape(chars)? {
+                Node::Char(c) => c,
+                Node::Class {
+                    ranges: escaped,
+                    negated: false,
+                } => {
+                    ranges.extend(escaped);
+                    first = false;
+                    continue;
+                }
+                _ => return Err("negated classes like \\D cannot be used inside [...]".to_string()),
+            },
+            Some(c) => c,
+            None => return Err("unclosed '['".to_string()),
+        };
+        first = false;
+
+        let is_range = chars.peek() == Some(&'-') && {
+            let mut lookahead = chars.clone();
+            lookahead.next();
+            lookahead.peek().is_some_and(|next| *next != ']')
+        };
+        if is_range {
+            chars.next();
+            let high = match chars.next() {
+                Some('\\') => match parse_escape(chars)? {
+                    Node::Char(high) => high,
+                    _ => return Err(format!("invalid range starting at '{c}'")),
+                },
+                Some(high) => high,
+                None => return Err("unclosed '['".to_string()),
+            };
+            if high < c {
+                return Err(format!("invalid range '{c}-{high}'"));
+            }
+            ranges.push((c, high));
+        } else {
+            ranges.push((c, c));
+        }
+    }
+    Ok(Node::Class { ranges, negated })
+}
+
+/// The character after a backslash
+fn parse_escape(chars: &mut Peekable<Chars>) -> Result<Node, String> {
+    const DIGIT: &[(char, char)] = &[('0', '9')];
+    const WORD: &[(char, char)] = &[('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')];
+    const SPACE: &[(char, char)] = &[(' ', ' '), ('\t', '\r')];
+
+    let class = |ranges: &[(char, char)], negated| Node::Class {
+        ranges: ranges.to_vec(),
+        negated,
+    };
+    match chars.next() {
+        Some('d') => Ok(class(DIGIT, false)),
+        Some('D') => Ok(class(DIGIT, true)),
+        Some('w') => Ok(class(WORD, false)),
+        Some('W') => Ok(class(WORD, true)),
+        Some('s') => Ok(class(SPACE, false)),
+        Some('S') => Ok(class(SPACE, true)),
+        Some('n') => Ok(Node::Char('\n')),
+        Some('t') => Ok(Node::Char('\t')),
+        Some(c) if c.is_alphanumeric() => Err(format!("unknown escape '\\{c}'")),
+        Some(c) => Ok(Node::Char(c)),
+        None => Err("trailing backslash".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    fn find(pattern: &str, text: &str) -> Option<Range<usize>> {
+        Pattern::new(pattern, false).unwrap().find(text)
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(find("bug", "fix the bug now"), Some(8..11));
+        assert_eq!(find("bug", "fix the Bug"), None);
+        assert_eq!(Pattern::literal("a.c", false).find("abc a.c"), Some(4..7));
+        assert_eq!(Pattern::literal("BUG", true).find("a bug"), Some(2..5));
+        // Byte offsets, not character positions
+        assert_eq!(find("été", "un été"), Some(3..8));
+    }
+
+    #[test]
+    fn classes() {
+        assert_eq!(find("[0-9]+", "room 101"), Some(5..8));
+        assert_eq!(find("[^a-z ]", "abc Def"), Some(4..5));
+        assert_eq!(find(r"\d{2}", "a1b23"), Some(3..5));
+        assert_eq!(find(r"\w+", "  hello_1 "), Some(2..9));
+        assert_eq!(find(r"\s", "ab c"), Some(2..3));
+        assert_eq!(find(r"[\d_]+", "x_12"), Some(1..4));
+        assert_eq!(find("[]a]", "b]"), Some(1..2));
+        assert_eq!(find("a.c", "a\nc abc"), Some(4..7));
+    }
+
+    #[test]
+    fn anchors() {
+        assert_eq!(find("^fix", "fix it"), Some(0..3));
+        assert_eq!(find("^fix", "prefix"), None);
+        assert_eq!(find("it$", "fix it"), Some(4..6));
+        assert_eq!(find("^$", ""), Some(0..0));
+        assert_eq!(find("^a$", "ab"), None);
+    }
+
+    #[test]
+    fn alternation_and_groups() {
+        assert_eq!(find("cat|dog", "hot dog"), Some(4..7));
+        assert_eq!(find("gr(a|e)y", "grey"), Some(0..4));
+        assert_eq!(find("(ab)+", "xababa"), Some(1..5));
+        assert_eq!(find("colou?r", "color"), Some(0..5));
+        assert_eq!(find("a{2,3}", "aaaa"), Some(0..3));
+        assert_eq!(find("a{2,}", "a aaaa"), Some(2..6));
+    }
+
+    #[test]
+    fn invalid_patterns() {
+        for pattern in [
+            "(a", "a)", "[a", "*a", "a{3,1}", r"\q", "^*", "[z-a]", "a\\",
+        ] {
+            assert!(Pattern::new(pattern, false).is_err(), "{pattern} parsed");
+        }
+    }
+
+    #[test]
+    fn ambiguous_patterns_stay_fast() {
+        let text = format!("{}b", "a".repeat(10_000));
+        let started = Instant::now();
+        assert_eq!(find("^(a|a)*c$", &text), None);
+        assert_eq!(find("(a*)*b", &text), Some(0..text.len()));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn prefers_what_backtracking_would_find() {
+        assert_eq!(find("a|ab", "ab"), Some(0..1));
+        assert_eq!(find("(a|ab)c", "abc"), Some(0..3));
+        assert_eq!(find("a?ab", "ab"), Some(0..2));
+        assert_eq!(find("a{1,2}a", "aaa"), Some(0..3));
+        assert_eq!(find("x*", "abc"), Some(0..0));
+        assert_eq!(find("(a|b)*b", "abab"), Some(0..4));
+    }
+
+    #[test]
+    fn huge_repetitions_are_refused() {
+        assert!(Pattern::new("(a{1000}){1000}", false).is_err());
+        assert!(Pattern::new("a{100000}", false).is_err());
+        assert_eq!(find("a{1000}", &"a".repeat(1000)), Some(0..1000));
+    }
+
+    #[test]
+    fn deep_nesting_is_refused() {
+        let nested = |depth: usize| format!("{}a{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(find(&nested(MAX_NESTING), "a"), Some(0..1));
+        assert!(Pattern::new(&nested(MAX_NESTING + 1), false).is_err());
+        assert!(Pattern::new(&nested(50_000), false).is_err());
+    }
+
+    #[test]
+    fn long_text_does_not_overflow() {
+        let text = "a".repeat(100_000);
+        assert_eq!(find("a*b", &text), None);
+        assert_eq!(find("b", &text), None);
+        assert_eq!(find("a{3}", &text), Some(0..3));
+        assert_eq!(find(".*", &text), Some(0..text.len()));
+
+        let pairs = "ab".repeat(50_000);
+        assert_eq!(find("(ab)*c", &pairs), None);
+        assert_eq!(find("(ab)+", &pairs), Some(0..pairs.len()));
+        assert_eq!(find("(ab)+$", &pairs[..800]), Some(0..800));
+        assert_eq!(find("^(a|b)*$", &pairs), Some(0..pairs.len()));
+    }
+}
@@ -0,0 +1,86 @@
+use std::{
+    fs::{self, File, OpenOptions, TryLockError},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// Copy of the previously saved version, e.g. tasks.json -> tasks.json.bak
+pub fn backup_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".bak")
+}
+
+/// Copies the current file to its backup path, doing nothing if it does not exist yet
+pub fn backup(path: &Path) -> io::Result<()> {
+    match fs::copy(path, backup_path(path)) {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Writes to a temporary file next to `path` and renames it into place, so a crash leaves
+/// either the old or the new contents behind, never a partial file
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let temp = with_suffix(path, ".tmp");
+    let result = write_synced(&temp, contents).and_then(|()| fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+        return result;
+    }
+
+    // The rename itself only survives a power loss once the directory is flushed too
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty())
+        && let Ok(dir) = File::open(dir)
+    {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+/// Exclusive advisory lock on a file, released when dropped. The OS also releases it when the
+/// process dies, so a crashed run never leaves a stale lock behind.
+pub struct Lock {
+    _file: File,
+}
+
+/// Lock file guarding `path`, e.g. tasks.json -> tasks.json.lock
+pub fn lock_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".lock")
+}
+
+/// Takes the lock guarding `path`, or returns `None` if another process holds it
+pub fn try_lock(path: &Path) -> io::Result<Option<Lock>> {
+    let file = open_lock_file(path)?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(Lock { _file: file })),
+        Err(TryLockError::WouldBlock) => Ok(None),
+        Err(TryLockError::Error(e)) => Err(e),
+    }
+}
+
+/// Takes the lock guarding `path`, waiting for other processes to release it
+pub fn lock(path: &Path) -> io::Result<Lock> {
+    let file = open_lock_file(path)?;
+    file.lock()?;
+    Ok(Lock { _file: file })
+}
+
+fn open_lock_file(path: &Path) -> io::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path(path))
+}
+
+fn write_synced(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
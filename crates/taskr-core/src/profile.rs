@@ -0,0 +1,60 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::persist;
+
+/// Profile whose tasks live directly in the data directory, as they did before profiles existed
+pub const DEFAULT_PROFILE: &str = "default";
+const PROFILES_DIR: &str = "profiles";
+/// Holds the name of the profile `context switch` selected
+const ACTIVE_FILE: &str = "profile";
+
+/// The profile selected with `context switch`, the default one if none was
+pub fn active(data_dir: &Path) -> String {
+    fs::read_to_string(data_dir.join(ACTIVE_FILE))
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+pub fn set_active(data_dir: &Path, name: &str) -> io::Result<()> {
+    if name == DEFAULT_PROFILE {
+        return match fs::remove_file(data_dir.join(ACTIVE_FILE)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    persist::write_atomic(&data_dir.join(ACTIVE_FILE), format!("{name}\n").as_bytes())
+}
+
+/// Directory holding the tasks of a profile
+pub fn dir(data_dir: &Path, name: &str) -> PathBuf {
+    if name == DEFAULT_PROFILE {
+        data_dir.to_path_buf()
+    } else {
+        data_dir.join(PROFILES_DIR).join(name)
+    }
+}
+
+/// Every profile that has a directory, the default one first and the rest sorted by name
+pub fn list(data_dir: &Path) -> io::Result<Vec<String>> {
+    let mut profiles = Vec::new();
+    match fs::read_dir(data_dir.join(PROFILES_DIR)) {
+        Ok(entries) => {
+            for entry in entries {
+                let entry = entry?;
+                if entry.file_type()?.is_dir() {
+                    profiles.push(entry.file_name().to_string_lossy().into_owned());
+                }
+            }
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    profiles.sort();
+    profiles.insert(0, DEFAULT_PROFILE.to_string());
+    Ok(profiles)
+}
@@ -0,0 +1,162 @@
+use std::{cmp::Reverse, ops::Range};
+
+use crate::{
+    pattern::Pattern,
+    store::{StoreError, Task, TaskStatus, TaskStore},
+};
+
+/// What tasks can be ordered by
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortKey {
+    Id,
+    /// Oldest first
+    Created,
+    /// Least recently updated first
+    Updated,
+    /// Soonest first, tasks without a due date last
+    Due,
+    /// Highest priority first, tasks without one last
+    Priority,
+    /// Todo, other statuses, someday and then complete
+    Status,
+    /// The order set by moving tasks
+    Manual,
+}
+
+/// Where a pattern first matched in each field of a task
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Match {
+    pub description: Option<Range<usize>>,
+    /// One entry per tag, in the task's order
+    pub tags: Vec<Option<Range<usize>>>,
+}
+
+/// Keeps the tasks that have all of `tags` and are in `project`, temporary tasks are left out
+pub fn filter_tasks(
+    tasks: &mut Vec<(u64, &Task)>,
+    store: &TaskStore,
+    tags: &[String],
+    project: Option<&str>,
+) -> Result<(), StoreError> {
+    tasks.retain(|(_, task)| !task.is_temporary() && tags.iter().all(|tag| task.has_tag(tag)));
+    if let Some(project) = project {
+        if !store.projects().contains(project) {
+            return Err(StoreError::ProjectNotFound(project.to_string()));
+        }
+        tasks.retain(|(_, task)| task.project.as_deref() == Some(project));
+    }
+    Ok(())
+}
+
+/// Ties are broken by ID, so the order is the same on every run
+pub fn sort_tasks(tasks: &mut [(u64, &Task)], sort: SortKey, reverse: bool) {
+    let status_rank = |status: &TaskStatus| match status {
+        TaskStatus::Todo => 0,
+        TaskStatus::InProgress | TaskStatus::Other(_) => 1,
+        TaskStatus::Someday => 2,
+        TaskStatus::Complete => 3,
+    };
+    match sort {
+        SortKey::Id => tasks.sort_by_key(|(id, _)| *id),
+        SortKey::Created => tasks.sort_by_key(|(id, task)| (task.created, *id)),
+        SortKey::Updated => tasks.sort_by_key(|(id, task)| (task.updated, *id)),
+        SortKey::Due => tasks.sort_by_key(|(id, task)| (task.due.is_none(), task.due, *id)),
+        SortKey::Priority => tasks.sort_by_key(|(id, task)| (Reverse(task.priority), *id)),
+        SortKey::Status => tasks.sort_by_key(|(id, task)| (status_rank(&task.status), *id)),
+        SortKey::Manual => tasks.sort_by(|(a_id, a), (b_id, b)| {
+            a.rank(*a_id).total_cmp(&b.rank(*b_id)).then(a_id.cmp(b_id))
+        }),
+    }
+    if reverse {
+        tasks.reverse();
+    }
+}
+
+/// Where `pattern` matches the task's description and tags, or `None` if it matches neither
+pub fn find(task: &Task, pattern: &Pattern) -> Option<Match> {
+    let found = Match {
+        description: pattern.find(&task.description),
+        tags: task.tags.iter().map(|tag| pattern.find(tag)).collect(),
+    };
+    if found.description.is_none() && found.tags.iter().all(Option::is_none) {
+        return None;
+    }
+    Some(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use time::{Duration, OffsetDateTime};
+
+    use super::*;
+    use crate::store::Priority;
+
+    fn store() -> TaskStore {
+        let now = OffsetDateTime::UNIX_EPOCH;
+        let mut store = TaskStore::open(env::temp_dir().join("taskr-query-missing.json")).unwrap();
+        for description in ["write docs", "fix bug", "release"] {
+            store.add(description.to_string(), now);
+        }
+        store.add_tag(1, "work", now).unwrap();
+        store.add_tag(2, "work", now).unwrap();
+        store.add_tag(2, "urgent", now).unwrap();
+        store.add_project("taskr");
+        store
+            .set_project(2, Some("taskr".to_string()), now)
+            .unwrap();
+        store.set_priority(3, Priority::High, now).unwrap();
+        store
+            .set_due(1, Some(now + Duration::days(2)), now)
+            .unwrap();
+        store
+            .set_due(3, Some(now + Duration::days(1)), now)
+            .unwrap();
+        store
+    }
+
+    fn ids(tasks: &[(u64, &Task)]) -> Vec<u64> {
+        tasks.iter().map(|(id, _)| *id).collect()
+    }
+
+    #[test]
+    fn filters_by_tags_and_project() {
+        let store = store();
+        let mut tasks = store.list(None);
+        filter_tasks(&mut tasks, &store, &["work".to_string()], None).unwrap();
+        sort_tasks(&mut tasks, SortKey::Id, false);
+        assert_eq!(ids(&tasks), [1, 2]);
+
+        filter_tasks(&mut tasks, &store, &[], Some("taskr")).unwrap();
+        assert_eq!(ids(&tasks), [2]);
+
+        let error = filter_tasks(&mut store.list(None), &store, &[], Some("nope"));
+        assert!(matches!(error, Err(StoreError::ProjectNotFound(_))));
+    }
+
+    #[test]
+    fn sorts_with_ties_broken_by_id() {
+        let store = store();
+        let mut tasks = store.list(None);
+        sort_tasks(&mut tasks, SortKey::Due, false);
+        assert_eq!(ids(&tasks), [3, 1, 2]);
+        sort_tasks(&mut tasks, SortKey::Priority, false);
+        assert_eq!(ids(&tasks), [3, 1, 2]);
+        sort_tasks(&mut tasks, SortKey::Created, true);
+        assert_eq!(ids(&tasks), [3, 2, 1]);
+    }
+
+    #[test]
+    fn finds_matches_in_description_and_tags() {
+        let store = store();
+        let task = store.get(2).unwrap();
+        let found = find(task, &Pattern::literal("urg", true)).unwrap();
+        assert_eq!(found.description, None);
+        assert_eq!(found.tags, [None, Some(0..3)]);
+
+        let found = find(task, &Pattern::literal("BUG", true)).unwrap();
+        assert_eq!(found.description, Some(4..7));
+        assert!(find(task, &Pattern::literal("docs", true)).is_none());
+    }
+}
@@ -0,0 +1,301 @@
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+use time::{Date, Duration, Month, OffsetDateTime, UtcOffset};
+
+const REPEAT_EXAMPLES: &str = "daily, weekly, monthly, yearly or \"every 3 days\"";
+/// The longest step a task can repeat by, so far off dates are refused up front
+const MAX_YEARS: u32 = 100;
+
+/// How often a recurring task comes back, stored as its text form, e.g. `every 2 weeks`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Recurrence {
+    Days(u32),
+    Weeks(u32),
+    Months(u32),
+    Years(u32),
+}
+
+impl Recurrence {
+    /// The occurrence after `from`, `None` when it lies past the dates `time` supports. Steps move
+    /// the calendar date and keep the wall clock time. Months that lack the day clamp to their last
+    /// day and the 31st always lands on the last day. The day itself is not remembered, so a
+    /// monthly task goes Jan 31 -> Feb 28 -> Mar 28, but Feb 28 never drifts to Mar 31.
+    ///
+    /// Times in the local time zone keep their wall clock time across daylight saving changes.
+    /// Other offsets stay fixed, as does everything when the local offset cannot be determined,
+    /// which `time` refuses once the process has several threads.
+    pub fn next(&self, from: OffsetDateTime) -> Option<OffsetDateTime> {
+        self.next_in(from, |at| UtcOffset::local_offset_at(at).ok())
+    }
+
+    /// `next` in the time zone whose offset at an instant `offset_at` gives
+    pub fn next_in(
+        &self,
+        from: OffsetDateTime,
+        offset_at: impl Fn(OffsetDateTime) -> Option<UtcOffset>,
+    ) -> Option<OffsetDateTime> {
+        let date = match *self {
+            Self::Days(n) => from.date().checked_add(Duration::days(n.into()))?,
+            Self::Weeks(n) => from.date().checked_add(Duration::weeks(n.into()))?,
+            Self::Months(n) => add_months(from.date(), n)?,
+            Self::Years(n) => add_months(from.date(), n.saturating_mul(12))?,
+        };
+        let next = from.replace_date(date);
+        if offset_at(from) != Some(from.offset()) {
+            return Some(next);
+        }
+        match offset_at(next) {
+            Some(offset) if offset != from.offset() => Some(next.replace_offset(offset)),
+            _ => Some(next),
+        }
+    }
+
+    /// Whether the step is at most `MAX_YEARS` long
+    fn in_range(&self) -> bool {
+        match *self {
+            Self::Days(n) => n <= MAX_YEARS * 366,
+            Self::Weeks(n) => n <= MAX_YEARS * 53,
+            Self::Months(n) => n <= MAX_YEARS * 12,
+            Self::Years(n) => n <= MAX_YEARS,
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid repeat '{s}', try {REPEAT_EXAMPLES}");
+
+        let s = s.trim().to_lowercase();
+        match s.as_str() {
+            "daily" => return Ok(Self::Days(1)),
+            "weekly" => return Ok(Self::Weeks(1)),
+            "monthly" => return Ok(Self::Months(1)),
+            "yearly" => return Ok(Self::Years(1)),
+            _ => {}
+        }
+
+        let rest = s.strip_prefix("every").ok_or_else(invalid)?;
+        let mut words = rest.split_whitespace();
+        let (amount, unit) = match (words.next(), words.next(), words.next()) {
+            (Some(unit), None, None) => (1, unit),
+            (Some(amount), Some(unit), None) => (amount.parse().map_err(|_| invalid())?, unit),
+            _ => return Err(invalid()),
+        };
+        if amount == 0 {
+            return Err(invalid());
+        }
+
+        let recurrence = match unit {
+            "day" | "days" => Self::Days(amount),
+            "week" | "weeks" => Self::Weeks(amount),
+            "month" | "months" => Self::Months(amount),
+            "year" | "years" => Self::Years(amount),
+            _ => return Err(invalid()),
+        };
+        if !recurrence.in_range() {
+            return Err(format!(
+                "invalid repeat '{s}', it can repeat at most every {MAX_YEARS} years"
+            ));
+        }
+        Ok(recurrence)
+    }
+}
+
+impl TryFrom<String> for Recurrence {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Recurrence> for String {
+    fn from(value: Recurrence) -> Self {
+        value.to_string()
+    }
+}
+
+impl Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (amount, unit) = match *self {
+            Self::Days(1) => return write!(f, "daily"),
+            Self::Weeks(1) => return write!(f, "weekly"),
+            Self::Months(1) => return write!(f, "monthly"),
+            Self::Years(1) => return write!(f, "yearly"),
+            Self::Days(n) => (n, "days"),
+            Self::Weeks(n) => (n, "weeks"),
+            Self::Months(n) => (n, "months"),
+            Self::Years(n) => (n, "years"),
+        };
+        write!(f, "every {amount} {unit}")
+    }
+}
+
+fn add_months(date: Date, months: u32) -> Option<Date> {
+    let index = date.year() as i64 * 12 + (date.month() as i64 - 1) + i64::from(months);
+    let year = i32::try_from(index.div_euclid(12)).ok()?;
+    let month = Month::try_from((index.rem_euclid(12) + 1) as u8).expect("Valid month");
+    let last_day = month.length(year);
+    let day = if date.day() == 31 {
+        last_day
+    } else {
+        date.day().min(last_day)
+    };
+
+    Date::from_calendar_date(year, month, day).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{Time, UtcOffset};
+
+    use super::*;
+
+    fn at(year: i32, month: u8, day: u8, hour: u8, offset: i8) -> OffsetDateTime {
+        let date = Date::from_calendar_date(year, month.try_into().unwrap(), day).unwrap();
+        let time = Time::from_hms(hour, 0, 0).unwrap();
+        date.with_time(time)
+            .assume_offset(UtcOffset::from_hms(offset, 0, 0).unwrap())
+    }
+
+    fn next(rule: &str, from: OffsetDateTime) -> OffsetDateTime {
+        rule.parse::<Recurrence>()
+            .unwrap()
+            .next_in(from, |_| Some(UtcOffset::UTC))
+            .unwrap()
+    }
+
+    /// US Eastern time around the start of daylight saving time on 2026-03-08
+    fn eastern(instant: OffsetDateTime) -> Option<UtcOffset> {
+        let hours = if instant < at(2026, 3, 8, 7, 0) {
+            -5
+        } else {
+            -4
+        };
+        UtcOffset::from_hms(hours, 0, 0).ok()
+    }
+
+    #[test]
+    fn month_end_clamps() {
+        assert_eq!(
+            next("monthly", at(2026, 1, 31, 9, 0)),
+            at(2026, 2, 28, 9, 0)
+        );
+        assert_eq!(
+            next("monthly", at(2024, 1, 31, 9, 0)),
+            at(2024, 2, 29, 9, 0)
+        );
+        assert_eq!(
+            next("every 2 months", at(2026, 1, 30, 9, 0)),
+            at(2026, 3, 30, 9, 0)
+        );
+    }
+
+    #[test]
+    fn the_31st_stays_last() {
+        let apr = next("every 3 months", at(2026, 1, 31, 9, 0));
+        assert_eq!(apr, at(2026, 4, 30, 9, 0));
+        assert_eq!(next("every 3 months", apr), at(2026, 7, 30, 9, 0));
+        assert_eq!(
+            next("every 6 months", at(2026, 1, 31, 9, 0)),
+            at(2026, 7, 31, 9, 0)
+        );
+    }
+
+    #[test]
+    fn other_days_do_not_drift() {
+        assert_eq!(
+            next("monthly", at(2027, 2, 28, 9, 0)),
+            at(2027, 3, 28, 9, 0)
+        );
+        assert_eq!(
+            next("monthly", at(2028, 2, 29, 9, 0)),
+            at(2028, 3, 29, 9, 0)
+        );
+        assert_eq!(
+            next("monthly", at(2026, 4, 30, 9, 0)),
+            at(2026, 5, 30, 9, 0)
+        );
+    }
+
+    #[test]
+    fn out_of_range_is_none() {
+        let from = at(9990, 6, 1, 9, 0);
+        let utc = |_| Some(UtcOffset::UTC);
+        assert_eq!(Recurrence::Years(10).next_in(from, utc), None);
+        assert_eq!(Recurrence::Days(36_600).next_in(from, utc), None);
+        assert_eq!(Recurrence::Weeks(5_300).next_in(from, utc), None);
+        assert_eq!(Recurrence::Months(u32::MAX).next_in(from, utc), None);
+    }
+
+    #[test]
+    fn leap_day_plus_a_year() {
+        assert_eq!(next("yearly", at(2024, 2, 29, 9, 0)), at(2025, 2, 28, 9, 0));
+        assert_eq!(
+            next("every 4 years", at(2024, 2, 29, 9, 0)),
+            at(2028, 2, 29, 9, 0)
+        );
+    }
+
+    #[test]
+    fn days_and_weeks() {
+        assert_eq!(
+            next("every 3 days", at(2026, 12, 30, 9, 0)),
+            at(2027, 1, 2, 9, 0)
+        );
+        assert_eq!(next("weekly", at(2026, 2, 26, 9, 0)), at(2026, 3, 5, 9, 0));
+    }
+
+    #[test]
+    fn local_time_survives_daylight_saving() {
+        let rule = Recurrence::Days(1);
+        let from = at(2026, 3, 7, 9, -5);
+        let next = rule.next_in(from, eastern);
+        assert_eq!(next, Some(at(2026, 3, 8, 9, -4)));
+        assert_eq!(
+            Recurrence::Weeks(1).next_in(from, eastern),
+            Some(at(2026, 3, 14, 9, -4))
+        );
+    }
+
+    #[test]
+    fn other_offsets_stay_fixed() {
+        let from = at(2026, 3, 7, 9, 0);
+        assert_eq!(
+            Recurrence::Days(1).next_in(from, eastern),
+            Some(at(2026, 3, 8, 9, 0))
+        );
+    }
+
+    #[test]
+    fn text_round_trips() {
+        for rule in [
+            "daily",
+            "weekly",
+            "monthly",
+            "yearly",
+            "every 3 days",
+            "every 2 weeks",
+        ] {
+            assert_eq!(rule.parse::<Recurrence>().unwrap().to_string(), rule);
+        }
+        assert_eq!("Every Day".parse(), Ok(Recurrence::Days(1)));
+        for rule in [
+            "every 0 days",
+            "every",
+            "fortnightly",
+            "every 2 fortnights",
+            "every 101 years",
+            "every 4000000 days",
+        ] {
+            assert!(rule.parse::<Recurrence>().is_err(), "{rule} parsed");
+        }
+    }
+}
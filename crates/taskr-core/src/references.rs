@@ -0,0 +1,72 @@
+/// Extracts `#<id>` references from free text, sorted and without duplicates.
+/// A `#` only starts a reference at the beginning of a word, so `issue#3`, `C#7` or `##3` are
+/// ignored. So are `#0`, which no task has, and IDs too large for a `u64`.
+pub fn parse_references(text: &str) -> Vec<u64> {
+    let mut ids = Vec::new();
+    let mut previous: Option<char> = None;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let at_word_start = previous.is_none_or(|p| !p.is_alphanumeric() && p != '#');
+        previous = Some(c);
+        if c != '#' || !at_word_start {
+            continue;
+        }
+
+        let digits_start = start + c.len_utf8();
+        let mut digits_end = digits_start;
+        while let Some((i, d)) = chars.peek().copied() {
+            if !d.is_ascii_digit() {
+                break;
+            }
+            digits_end = i + d.len_utf8();
+            previous = Some(d);
+            chars.next();
+        }
+
+        // `#12abc` is a word, not a reference
+        if chars.peek().is_some_and(|(_, c)| c.is_alphanumeric()) {
+            continue;
+        }
+        if let Ok(id) = text[digits_start..digits_end].parse::<u64>()
+            && id > 0
+        {
+            ids.push(id);
+        }
+    }
+
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn references_start_words() {
+        assert_eq!(parse_references("#12 after #3, see #12"), [3, 12]);
+        assert!(parse_references("issue#12 C#7 done#").is_empty());
+        assert!(parse_references("#12abc #4a").is_empty());
+        assert_eq!(parse_references("é#5 über #6"), [6]);
+    }
+
+    #[test]
+    fn trailing_punctuation_ends_a_reference() {
+        assert_eq!(
+            parse_references("(#1) #2, #3. #4: #5! \"#6\" [#7]"),
+            [1, 2, 3, 4, 5, 6, 7]
+        );
+    }
+
+    #[test]
+    fn no_task_has_these() {
+        assert!(parse_references("#0 #00 ##3 # #").is_empty());
+        assert_eq!(parse_references("#007"), [7]);
+        assert_eq!(
+            parse_references("#18446744073709551615 #18446744073709551616 #99999999999999999999"),
+            [u64::MAX]
+        );
+    }
+}
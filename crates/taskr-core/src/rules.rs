@@ -0,0 +1,193 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{parse, pattern::Pattern, store::Task};
+
+pub const RULES_FILE: &str = "rules.json";
+
+/// Tags and a project applied on add to tasks whose description mentions one of the keywords or
+/// matches one of the patterns, e.g.
+/// `[{"name": "bills", "keywords": ["invoice"], "patterns": ["^pay "], "tags": ["finance"]}]`
+#[derive(Debug)]
+pub struct Rule {
+    pub name: String,
+    pub keywords: Vec<String>,
+    /// Regular expressions in the syntax `search --regex` takes
+    pub patterns: Vec<Pattern>,
+    pub tags: Vec<String>,
+    pub project: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRule {
+    name: String,
+    #[serde(default)]
+    keywords: Vec<String>,
+    #[serde(default)]
+    patterns: Vec<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    project: Option<String>,
+}
+
+impl Rule {
+    /// Keywords and patterns match case-insensitively anywhere in the description, so invoice
+    /// matches Invoices
+    pub fn matches(&self, description: &str) -> bool {
+        let lowercase = description.to_lowercase();
+        self.keywords
+            .iter()
+            .any(|keyword| lowercase.contains(&keyword.to_lowercase()))
+            || self
+                .patterns
+                .iter()
+                .any(|pattern| pattern.find(description).is_some())
+    }
+}
+
+#[derive(Debug)]
+pub enum RuleError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, serde_json::Error),
+    Invalid(PathBuf, String),
+}
+
+impl Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(p, io_err) => write!(f, "I/O error on {p:?}: {io_err}"),
+            Self::Parse(p, json_err) => write!(f, "{p:?} is not a valid rules file: {json_err}"),
+            Self::Invalid(p, message) => write!(f, "Invalid rule in {p:?}: {message}"),
+        }
+    }
+}
+
+impl Error for RuleError {}
+
+/// Reads the rules at `path`, a missing file means there are no rules
+pub fn load(path: &Path) -> Result<Vec<Rule>, RuleError> {
+    let json = match fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(RuleError::Io(path.to_path_buf(), e)),
+    };
+    let raw: Vec<RawRule> =
+        serde_json::from_str(&json).map_err(|e| RuleError::Parse(path.to_path_buf(), e))?;
+
+    let invalid = |message: String| RuleError::Invalid(path.to_path_buf(), message);
+    let mut rules = Vec::new();
+    for raw in raw {
+        let keywords = raw
+            .keywords
+            .into_iter()
+            .filter(|keyword| !keyword.trim().is_empty())
+            .collect::<Vec<_>>();
+        if keywords.is_empty() && raw.patterns.is_empty() {
+            return Err(invalid(format!(
+                "rule '{}' has no keywords or patterns",
+                raw.name
+            )));
+        }
+        let patterns = raw
+            .patterns
+            .iter()
+            .map(|pattern| {
+                Pattern::new(pattern, true).map_err(|e| {
+                    invalid(format!(
+                        "rule '{}' has an invalid pattern '{pattern}': {e}",
+                        raw.name
+                    ))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        let tags = raw
+            .tags
+            .iter()
+            .map(|tag| parse::parse_tag(tag))
+            .collect::<Result<_, _>>()
+            .map_err(invalid)?;
+        let project = raw
+            .project
+            .as_deref()
+            .map(parse::parse_project)
+            .transpose()
+            .map_err(invalid)?;
+        rules.push(Rule {
+            name: raw.name,
+            keywords,
+            patterns,
+            tags,
+            project,
+        });
+    }
+    Ok(rules)
+}
+
+/// Applies every matching rule to `task`, returning the names of those that matched. A project
+/// given explicitly is kept, rules only fill it in when the task has none.
+pub fn apply<'a>(rules: &'a [Rule], task: &mut Task) -> Vec<&'a str> {
+    let mut applied = Vec::new();
+    for rule in rules.iter().filter(|rule| rule.matches(&task.description)) {
+        for tag in &rule.tags {
+            if !task.has_tag(tag) {
+                task.tags.push(tag.clone());
+            }
+        }
+        if task.project.is_none() {
+            task.project = rule.project.clone();
+        }
+        applied.push(rule.name.as_str());
+    }
+    applied
+}
+
+#[cfg(test)]
+mod tests {
+    use time::OffsetDateTime;
+
+    use super::*;
+
+    fn rule(keywords: &[&str], patterns: &[&str]) -> Rule {
+        Rule {
+            name: "bills".to_string(),
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            patterns: patterns
+                .iter()
+                .map(|p| Pattern::new(p, true).unwrap())
+                .collect(),
+            tags: vec!["finance".to_string()],
+            project: Some("home".to_string()),
+        }
+    }
+
+    #[test]
+    fn keywords_and_patterns_match() {
+        let rule = rule(&["invoice"], &[r"^pay \w+$"]);
+        assert!(rule.matches("Send the INVOICES"));
+        assert!(rule.matches("Pay rent"));
+        assert!(!rule.matches("pay the rent"));
+        assert!(!rule.matches("repay rent"));
+    }
+
+    #[test]
+    fn apply_keeps_an_explicit_project() {
+        let rules = [rule(&["invoice"], &[])];
+        let mut task = Task::new("invoice Sam".to_string(), OffsetDateTime::UNIX_EPOCH);
+        task.project = Some("work".to_string());
+        assert_eq!(apply(&rules, &mut task), ["bills"]);
+        assert_eq!(task.tags, ["finance"]);
+        assert_eq!(task.project.as_deref(), Some("work"));
+
+        let mut task = Task::new("groceries".to_string(), OffsetDateTime::UNIX_EPOCH);
+        assert!(apply(&rules, &mut task).is_empty());
+        assert!(task.tags.is_empty());
+    }
+}
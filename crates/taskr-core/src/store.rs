@@ -0,0 +1,1462 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    error::Error,
+    fmt::{self, Display},
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use time::{Duration, OffsetDateTime};
+
+use crate::{
+    condition::Condition,
+    integrity,
+    journal::{self, Change, Entry, Operation, ProjectsChange},
+    persist,
+    recurrence::Recurrence,
+    references,
+};
+
+const LEGACY_ID_FILE: &str = "next_id.txt";
+/// Status of tasks waiting on something, set by `block`
+pub const BLOCKED: &str = "blocked";
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(from = "StoredStatus")]
+pub enum TaskStatus {
+    Todo,
+    InProgress,
+    Complete,
+    Someday,
+    Other(String),
+}
+
+/// `TaskStatus` as stored, which includes the custom in-progress status older versions wrote
+/// before it had its own variant
+#[derive(Deserialize)]
+enum StoredStatus {
+    Todo,
+    InProgress,
+    Complete,
+    Someday,
+    Other(String),
+}
+
+impl From<StoredStatus> for TaskStatus {
+    fn from(status: StoredStatus) -> Self {
+        match status {
+            StoredStatus::Todo => Self::Todo,
+            StoredStatus::InProgress => Self::InProgress,
+            StoredStatus::Complete => Self::Complete,
+            StoredStatus::Someday => Self::Someday,
+            StoredStatus::Other(other) if other == "in-progress" => Self::InProgress,
+            StoredStatus::Other(other) => Self::Other(other),
+        }
+    }
+}
+
+impl TaskStatus {
+    /// Not completed and not put off to someday
+    pub fn is_open(&self) -> bool {
+        matches!(self, Self::Todo | Self::InProgress | Self::Other(_))
+    }
+}
+
+impl FromStr for TaskStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str().trim() {
+            "todo" => Ok(TaskStatus::Todo),
+            "in-progress" | "in progress" => Ok(TaskStatus::InProgress),
+            "complete" => Ok(TaskStatus::Complete),
+            "someday" => Ok(TaskStatus::Someday),
+            other => Ok(TaskStatus::Other(other.to_string())),
+        }
+    }
+}
+
+impl Display for TaskStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskStatus::Todo => write!(f, "Todo"),
+            TaskStatus::InProgress => write!(f, "In progress"),
+            TaskStatus::Complete => write!(f, "Complete"),
+            TaskStatus::Someday => write!(f, "Someday"),
+            TaskStatus::Other(other) => write!(f, "{other}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl FromStr for Priority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str().trim() {
+            "low" | "l" => Ok(Priority::Low),
+            "medium" | "med" | "m" => Ok(Priority::Medium),
+            "high" | "h" => Ok(Priority::High),
+            "critical" | "crit" | "c" => Ok(Priority::Critical),
+            other => Err(format!(
+                "unknown priority '{other}', expected low, medium, high or critical"
+            )),
+        }
+    }
+}
+
+impl Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Priority::Low => write!(f, "Low"),
+            Priority::Medium => write!(f, "Medium"),
+            Priority::High => write!(f, "High"),
+            Priority::Critical => write!(f, "Critical"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Task {
+    pub description: String,
+    pub status: TaskStatus,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated: OffsetDateTime,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "time::serde::rfc3339::option"
+    )]
+    pub reviewed: Option<OffsetDateTime>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "time::serde::rfc3339::option"
+    )]
+    pub due: Option<OffsetDateTime>,
+    /// How long before the due date the shell summary starts reminding of the task, stored in
+    /// seconds
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "seconds")]
+    pub remind: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Position in the manual order, tasks that were never moved sort by ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rank: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<u64>,
+    /// Tasks that have to be completed before this one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat: Option<Recurrence>,
+    /// Shell session a temporary task belongs to, temporary tasks stay out of the normal list
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    /// When a temporary task is removed even if its session never clears it
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "time::serde::rfc3339::option"
+    )]
+    pub expires: Option<OffsetDateTime>,
+    /// External conditions that all have to hold before a blocked task goes back to Todo
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub waiting_on: Vec<Condition>,
+    /// Oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<Note>,
+    /// UUID of the task in Taskwarrior, kept so exporting maps it back to the same task
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Note {
+    #[serde(with = "time::serde::rfc3339")]
+    pub time: OffsetDateTime,
+    pub text: String,
+}
+
+impl Task {
+    pub fn new(description: String, now: OffsetDateTime) -> Self {
+        Self {
+            description,
+            status: TaskStatus::Todo,
+            created: now,
+            updated: now,
+            reviewed: None,
+            due: None,
+            remind: None,
+            priority: None,
+            tags: Vec::new(),
+            rank: None,
+            project: None,
+            parent: None,
+            depends_on: Vec::new(),
+            repeat: None,
+            session: None,
+            expires: None,
+            waiting_on: Vec::new(),
+            notes: Vec::new(),
+            uuid: None,
+        }
+    }
+
+    pub fn is_temporary(&self) -> bool {
+        self.session.is_some()
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /// Fractional position used by `--sort manual`
+    pub fn rank(&self, id: u64) -> f64 {
+        self.rank.unwrap_or(id as f64)
+    }
+
+    /// When reminding of the task starts, if it has a due date and a reminder
+    pub fn reminder(&self) -> Option<OffsetDateTime> {
+        self.due?.checked_sub(self.remind?)
+    }
+
+    /// Past its due date and not done yet
+    pub fn is_overdue(&self, now: OffsetDateTime) -> bool {
+        self.status != TaskStatus::Complete && self.due.is_some_and(|due| due < now)
+    }
+
+    /// Days the due day of an open task lies behind today, i.e. how often it rolled over to the
+    /// next day. Someday, completed and temporary tasks never roll over.
+    pub fn days_rolled_over(&self, now: OffsetDateTime) -> Option<i64> {
+        let open = self.status.is_open();
+        let due = self.due.filter(|_| open && !self.is_temporary())?;
+        let days = (now.date() - due.to_offset(now.offset()).date()).whole_days();
+        (days > 0).then_some(days)
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+struct TaskContainer {
+    // Lives next to the tasks so an ID is only ever consumed together with its task
+    #[serde(default)]
+    next_id: u64,
+    tasks: HashMap<u64, Task>,
+    // Tracked separately from the tasks so projects can exist while empty
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    projects: BTreeSet<String>,
+}
+
+#[derive(Debug)]
+pub enum StoreError {
+    NotFound(u64),
+    HasSubtasks(u64, Vec<u64>),
+    DependencyCycle(u64, u64),
+    ProjectNotFound(String),
+    ProjectExists(String),
+    /// A task was changed after the operation being undone or redone, with the operation's label
+    JournalConflict(u64, String),
+    /// Completing a recurring task would put its next occurrence past the supported dates
+    RepeatOutOfRange(u64),
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, serde_json::Error),
+}
+
+impl Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(id) => write!(f, "No task found with ID: {id}"),
+            Self::HasSubtasks(id, children) => {
+                let children = children
+                    .iter()
+                    .map(|id| format!("#{id}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "Task {id} still has subtasks: {children}")
+            }
+            Self::DependencyCycle(id, on) => {
+                write!(
+                    f,
+                    "Task {id} cannot depend on task {on}, {on} already depends on {id}"
+                )
+            }
+            Self::ProjectNotFound(name) => write!(f, "No project named: {name}"),
+            Self::ProjectExists(name) => write!(f, "Project already exists: {name}"),
+            Self::JournalConflict(id, label) => write!(
+                f,
+                "Task {id} no longer matches the journal for '{label}', it was changed in a way \
+                 the journal did not record"
+            ),
+            Self::RepeatOutOfRange(id) => write!(
+                f,
+                "Task {id} cannot repeat, its next occurrence is past the last supported date"
+            ),
+            Self::Io(p, io_err) => write!(f, "I/O error on {p:?}: {io_err}"),
+            Self::Parse(p, json_err) => write!(f, "{p:?} is not a valid tasks file: {json_err}"),
+        }
+    }
+}
+
+impl Error for StoreError {}
+
+/// Tasks persisted in a single JSON file, with the operations the CLI is built on
+pub struct TaskStore {
+    path: PathBuf,
+    container: TaskContainer,
+    checksum_mismatch: bool,
+    /// The container as last loaded or saved, diffed against on save to journal the operation
+    baseline: TaskContainer,
+    /// Describes the operation in the journal, e.g. the command that made it, and when it ran
+    label: String,
+    time: OffsetDateTime,
+    /// Undo and redo entries to journal on save instead of a diff
+    pending: Vec<Entry>,
+    journaled: bool,
+    /// Tasks the next save moves in or out of the archive
+    archived: BTreeSet<u64>,
+    /// What undo and redo moved out of or back into the task list, for the archive to follow
+    archive_moves: ArchiveMoves,
+}
+
+/// Tasks an undo or redo took out of the task list that belong in the archive, and tasks it put
+/// back that have to leave the archive
+#[derive(Debug, Default)]
+pub struct ArchiveMoves {
+    pub archive: Vec<(u64, Task)>,
+    pub unarchive: Vec<u64>,
+}
+
+impl TaskStore {
+    /// Opens the store at `path`, starting out empty if the file does not exist yet
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<Self, StoreError> {
+        let path = path.into();
+        if !path.exists() {
+            let container = TaskContainer {
+                next_id: 1,
+                tasks: HashMap::new(),
+                projects: BTreeSet::new(),
+            };
+            return Ok(Self::new(path, container, false));
+        }
+        Self::load(path)
+    }
+
+    /// Opens the store like `open`. When the file does not match its checksum but its backup
+    /// does, the backup is restored and the mismatching file is kept aside at the returned path.
+    pub fn open_verified<P: Into<PathBuf>>(path: P) -> Result<(Self, Option<PathBuf>), StoreError> {
+        let path = path.into();
+        let store = Self::open(path.clone())?;
+        if !store.checksum_mismatch {
+            return Ok((store, None));
+        }
+
+        let backup = persist::backup_path(&path);
+        let sidecar = integrity::sidecar_path(&path);
+        let backup_sidecar = persist::backup_path(&sidecar);
+        let Ok(contents) = fs::read(&backup) else {
+            return Ok((store, None));
+        };
+        if integrity::matches(&backup_sidecar, &contents) != Some(true) {
+            return Ok((store, None));
+        }
+
+        let mut kept = path.as_os_str().to_owned();
+        kept.push(".mismatch");
+        let kept = PathBuf::from(kept);
+        fs::copy(&path, &kept).map_err(|e| StoreError::Io(kept.clone(), e))?;
+        integrity::expect_checksum(&path, &contents)
+            .map_err(|e| StoreError::Io(sidecar.clone(), e))?;
+        persist::write_atomic(&path, &contents).map_err(|e| StoreError::Io(path.clone(), e))?;
+        integrity::write_checksum(&path, &contents).map_err(|e| StoreError::Io(sidecar, e))?;
+        Ok((Self::load(path)?, Some(kept)))
+    }
+
+    /// Loads a store file that has to exist, e.g. a snapshot
+    pub fn load<P: Into<PathBuf>>(path: P) -> Result<Self, StoreError> {
+        let path = path.into();
+        let json = fs::read_to_string(&path).map_err(|e| StoreError::Io(path.clone(), e))?;
+        let checksum_mismatch = !integrity::verify(&path, json.as_bytes());
+        let mut container: TaskContainer =
+            serde_json::from_str(&json).map_err(|e| StoreError::Parse(path.clone(), e))?;
+
+        // Stores written before the counter moved into tasks.json have no next_id yet
+        if container.next_id == 0 {
+            let highest_id = container.tasks.keys().max().copied().unwrap_or(0);
+            let legacy_id = read_legacy_id(&path.with_file_name(LEGACY_ID_FILE))?;
+            container.next_id = legacy_id.max(highest_id) + 1;
+        }
+
+        Ok(Self::new(path, container, checksum_mismatch))
+    }
+
+    fn new(path: PathBuf, container: TaskContainer, checksum_mismatch: bool) -> Self {
+        Self {
+            path,
+            baseline: container.clone(),
+            container,
+            checksum_mismatch,
+            label: String::new(),
+            time: OffsetDateTime::now_utc(),
+            pending: Vec::new(),
+            journaled: true,
+            archived: BTreeSet::new(),
+            archive_moves: ArchiveMoves::default(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether anything has been saved to disk yet
+    pub fn exists(&self) -> bool {
+        self.path.exists()
+    }
+
+    /// The file on disk did not match its checksum when it was loaded
+    pub fn checksum_mismatch(&self) -> bool {
+        self.checksum_mismatch
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.container).expect("Failed to serialize container")
+    }
+
+    /// Stops saves from journaling, for stores undo does not cover such as the archive
+    pub fn disable_journal(&mut self) {
+        self.journaled = false;
+    }
+
+    /// Saves without journaling, for housekeeping the user did not ask for such as expiring
+    /// temporary tasks. The changes are not attributed to the next operation either.
+    pub fn save_unjournaled(&mut self) -> Result<(), StoreError> {
+        let journaled = std::mem::replace(&mut self.journaled, false);
+        let saved = self.save();
+        self.journaled = journaled;
+        if saved.is_ok() {
+            self.baseline = self.container.clone();
+        }
+        saved
+    }
+
+    /// Records that `id` moved between this task list and the archive, so undoing the operation
+    /// moves it back
+    pub fn mark_archived(&mut self, id: u64) {
+        self.archived.insert(id);
+    }
+
+    /// The archive moves of the last undo or redo, to apply to the archive
+    pub fn take_archive_moves(&mut self) -> ArchiveMoves {
+        std::mem::take(&mut self.archive_moves)
+    }
+
+    /// What the journal records about the next save
+    pub fn set_operation(&mut self, label: String, now: OffsetDateTime) {
+        self.label = label;
+        self.time = now;
+    }
+
+    /// Replaces the file atomically, keeping the previous version as a .bak, and journals what
+    /// changed since the last load or save
+    pub fn save(&mut self) -> Result<(), StoreError> {
+        let json = self.to_json();
+        let sidecar = integrity::sidecar_path(&self.path);
+        // The backup may be the last version known to be good, a file that failed its checksum
+        // must not replace it
+        if !self.checksum_mismatch {
+            for path in [&self.path, &sidecar] {
+                persist::backup(path).map_err(|e| StoreError::Io(persist::backup_path(path), e))?;
+            }
+        }
+        // Both checksums are accepted until the tasks are replaced, a crash in between must not
+        // make the next load roll back to the backup
+        let expected = integrity::expect_checksum(&self.path, json.as_bytes());
+        persist::write_atomic(&self.path, json.as_bytes())
+            .map_err(|e| StoreError::Io(self.path.clone(), e))?;
+
+        // The tasks are saved at this point, the sidecars are attempted even if one fails
+        let checksum = expected
+            .and_then(|()| integrity::write_checksum(&self.path, json.as_bytes()))
+            .map_err(|e| {
+                // A stale checksum would report the saved tasks as modified on the next load
+                let _ = fs::remove_file(&sidecar);
+                StoreError::Io(sidecar.clone(), e)
+            });
+        let journaled = if self.journaled {
+            self.journal()
+        } else {
+            Ok(())
+        };
+
+        // The counter has been migrated into tasks.json, the old file would only go stale
+        let legacy_id_file = self.path.with_file_name(LEGACY_ID_FILE);
+        if legacy_id_file.exists() {
+            let _ = fs::remove_file(legacy_id_file);
+        }
+        checksum.and(journaled)
+    }
+
+    /// Appends the pending undo and redo entries, or otherwise the changes since the baseline
+    /// as a new operation
+    fn journal(&mut self) -> Result<(), StoreError> {
+        let path = journal::journal_path(&self.path);
+        let baseline = std::mem::replace(&mut self.baseline, self.container.clone());
+        let mut entries = std::mem::take(&mut self.pending);
+        if entries.is_empty() {
+            let mut ids = baseline
+                .tasks
+                .keys()
+                .chain(self.container.tasks.keys())
+                .copied()
+                .collect::<Vec<_>>();
+            ids.sort_unstable();
+            ids.dedup();
+
+            let changes = ids
+                .into_iter()
+                .filter_map(|id| {
+                    let before = baseline.tasks.get(&id);
+                    let after = self.container.tasks.get(&id);
+                    (before != after).then(|| Change {
+                        id,
+                        before: before.cloned(),
+                        after: after.cloned(),
+                    })
+                })
+                .collect::<Vec<_>>();
+            let projects = (baseline.projects != self.container.projects).then(|| ProjectsChange {
+                before: baseline.projects,
+                after: self.container.projects.clone(),
+            });
+            if changes.is_empty() && projects.is_none() {
+                return Ok(());
+            }
+
+            let existing = journal::read(&path).map_err(|e| StoreError::Io(path.clone(), e))?;
+            entries.push(Entry::Op(Operation {
+                seq: journal::next_seq(&existing),
+                time: self.time,
+                label: self.label.clone(),
+                changes,
+                projects,
+                archived: std::mem::take(&mut self.archived).into_iter().collect(),
+            }));
+        }
+        journal::append(&path, &entries).map_err(|e| StoreError::Io(path, e))
+    }
+
+    /// Reverts the last `count` operations that have not been undone yet, returning their labels
+    pub fn undo(&mut self, count: usize, now: OffsetDateTime) -> Result<Vec<String>, StoreError> {
+        let path = journal::journal_path(&self.path);
+        let entries = journal::read(&path).map_err(|e| StoreError::Io(path, e))?;
+        let (done, _) = journal::stacks(&entries);
+
+        let mut labels = Vec::new();
+        for op in done.into_iter().rev().take(count) {
+            self.replay(op, true)?;
+            self.pending.push(Entry::Undo {
+                seq: op.seq,
+                time: now,
+            });
+            labels.push(op.label.clone());
+        }
+        Ok(labels)
+    }
+
+    /// Reapplies the last `count` undone operations, returning their labels. Operations undone
+    /// before a new change was made can no longer be redone.
+    pub fn redo(&mut self, count: usize, now: OffsetDateTime) -> Result<Vec<String>, StoreError> {
+        let path = journal::journal_path(&self.path);
+        let entries = journal::read(&path).map_err(|e| StoreError::Io(path, e))?;
+        let (_, undone) = journal::stacks(&entries);
+
+        let mut labels = Vec::new();
+        for op in undone.into_iter().rev().take(count) {
+            self.replay(op, false)?;
+            self.pending.push(Entry::Redo {
+                seq: op.seq,
+                time: now,
+            });
+            labels.push(op.label.clone());
+        }
+        Ok(labels)
+    }
+
+    /// Moves the tasks of `op` to their state before it, or after it when not `reverse`. Every
+    /// task has to be in the state the operation left it in, or already in the one it is moved
+    /// to, e.g. an expired temporary task, so later changes that were not journaled are never
+    /// overwritten.
+    fn replay(&mut self, op: &Operation, reverse: bool) -> Result<(), StoreError> {
+        let states = |change: &Change| {
+            if reverse {
+                (change.after.clone(), change.before.clone())
+            } else {
+                (change.before.clone(), change.after.clone())
+            }
+        };
+        for change in &op.changes {
+            let (expected, target) = states(change);
+            let current = self.container.tasks.get(&change.id);
+            if current != expected.as_ref() && current != target.as_ref() {
+                return Err(StoreError::JournalConflict(change.id, op.label.clone()));
+            }
+        }
+
+        for change in &op.changes {
+            let (expected, target) = states(change);
+            let moved = self.container.tasks.get(&change.id) != target.as_ref();
+            if moved && op.archived.contains(&change.id) {
+                match &target {
+                    Some(_) => self.archive_moves.unarchive.push(change.id),
+                    None => self
+                        .archive_moves
+                        .archive
+                        .push((change.id, expected.expect("An archived task existed"))),
+                }
+            }
+            match target {
+                Some(task) => self.container.tasks.insert(change.id, task),
+                None => self.container.tasks.remove(&change.id),
+            };
+        }
+        replay_projects(&mut self.container, op, reverse);
+        Ok(())
+    }
+
+    /// The tasks as they were at `time`, worked out by reverting what the journal recorded
+    /// since then. Changes that were not journaled, such as expired temporary tasks, stay.
+    pub fn as_of(&self, time: OffsetDateTime) -> Result<TaskStore, StoreError> {
+        let path = journal::journal_path(&self.path);
+        let entries = journal::read(&path).map_err(|e| StoreError::Io(path, e))?;
+        let operations = entries
+            .iter()
+            .filter_map(|entry| match entry {
+                Entry::Op(op) => Some((op.seq, op)),
+                _ => None,
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut container = self.container.clone();
+        for entry in entries.iter().rev() {
+            // Undoing an undo applies the operation again, undoing a redo reverts it
+            let (seq, at, reverse) = match entry {
+                Entry::Op(op) => (op.seq, op.time, true),
+                Entry::Undo { seq, time } => (*seq, *time, false),
+                Entry::Redo { seq, time } => (*seq, *time, true),
+            };
+            if at <= time {
+                break;
+            }
+            let Some(op) = operations.get(&seq) else {
+                continue;
+            };
+            for change in &op.changes {
+                let state = if reverse {
+                    &change.before
+                } else {
+                    &change.after
+                };
+                match state {
+                    Some(task) => container.tasks.insert(change.id, task.clone()),
+                    None => container.tasks.remove(&change.id),
+                };
+            }
+            replay_projects(&mut container, op, reverse);
+        }
+        Ok(Self::new(self.path.clone(), container, false))
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Task> {
+        self.container.tasks.get(&id)
+    }
+
+    pub fn tasks(&self) -> &HashMap<u64, Task> {
+        &self.container.tasks
+    }
+
+    /// All tasks, or only those with `status`
+    pub fn list(&self, status: Option<&TaskStatus>) -> Vec<(u64, &Task)> {
+        self.container
+            .tasks
+            .iter()
+            .filter(|(_, task)| status.is_none_or(|status| task.status == *status))
+            .map(|(id, task)| (*id, task))
+            .collect()
+    }
+
+    pub fn add(&mut self, description: String, now: OffsetDateTime) -> u64 {
+        self.insert(Task::new(description, now))
+    }
+
+    /// Stores a fully built task under a freshly allocated ID
+    pub fn insert(&mut self, task: Task) -> u64 {
+        if let Some(project) = &task.project {
+            self.container.projects.insert(project.clone());
+        }
+
+        let id = self.container.next_id;
+        self.container.next_id += 1;
+        self.container.tasks.insert(id, task);
+        id
+    }
+
+    pub fn update(
+        &mut self,
+        id: u64,
+        description: String,
+        now: OffsetDateTime,
+    ) -> Result<(), StoreError> {
+        let task = self.get_mut(id)?;
+        task.description = description;
+        task.updated = now;
+        Ok(())
+    }
+
+    pub fn set_due(
+        &mut self,
+        id: u64,
+        due: Option<OffsetDateTime>,
+        now: OffsetDateTime,
+    ) -> Result<(), StoreError> {
+        let task = self.get_mut(id)?;
+        task.due = due;
+        task.updated = now;
+        Ok(())
+    }
+
+    pub fn set_remind(
+        &mut self,
+        id: u64,
+        remind: Option<Duration>,
+        now: OffsetDateTime,
+    ) -> Result<(), StoreError> {
+        let task = self.get_mut(id)?;
+        task.remind = remind;
+        task.updated = now;
+        Ok(())
+    }
+
+    pub fn set_priority(
+        &mut self,
+        id: u64,
+        priority: Priority,
+        now: OffsetDateTime,
+    ) -> Result<(), StoreError> {
+        let task = self.get_mut(id)?;
+        task.priority = Some(priority);
+        task.updated = now;
+        Ok(())
+    }
+
+    pub fn add_note(
+        &mut self,
+        id: u64,
+        text: String,
+        now: OffsetDateTime,
+    ) -> Result<(), StoreError> {
+        let task = self.get_mut(id)?;
+        task.notes.push(Note { time: now, text });
+        task.updated = now;
+        Ok(())
+    }
+
+    /// Returns whether the tag was newly added
+    pub fn add_tag(&mut self, id: u64, tag: &str, now: OffsetDateTime) -> Result<bool, StoreError> {
+        let task = self.get_mut(id)?;
+        if task.has_tag(tag) {
+            return Ok(false);
+        }
+        task.tags.push(tag.to_string());
+        task.updated = now;
+        Ok(true)
+    }
+
+    /// Returns whether the task had the tag
+    pub fn remove_tag(
+        &mut self,
+        id: u64,
+        tag: &str,
+        now: OffsetDateTime,
+    ) -> Result<bool, StoreError> {
+        let task = self.get_mut(id)?;
+        if !task.has_tag(tag) {
+            return Ok(false);
+        }
+        task.tags.retain(|t| t != tag);
+        task.updated = now;
+        Ok(true)
+    }
+
+    pub fn set_project(
+        &mut self,
+        id: u64,
+        project: Option<String>,
+        now: OffsetDateTime,
+    ) -> Result<(), StoreError> {
+        let task = self.get_mut(id)?;
+        task.project = project.clone();
+        task.updated = now;
+
+        if let Some(project) = project {
+            self.container.projects.insert(project);
+        }
+        Ok(())
+    }
+
+    pub fn projects(&self) -> &BTreeSet<String> {
+        &self.container.projects
+    }
+
+    /// Returns whether the project was newly created
+    pub fn add_project(&mut self, name: &str) -> bool {
+        self.container.projects.insert(name.to_string())
+    }
+
+    /// Renames the project on every task in it, returning how many tasks moved
+    pub fn rename_project(
+        &mut self,
+        old: &str,
+        new: &str,
+        now: OffsetDateTime,
+    ) -> Result<usize, StoreError> {
+        if !self.container.projects.contains(old) {
+            return Err(StoreError::ProjectNotFound(old.to_string()));
+        }
+        if self.container.projects.contains(new) {
+            return Err(StoreError::ProjectExists(new.to_string()));
+        }
+
+        self.container.projects.remove(old);
+        self.container.projects.insert(new.to_string());
+        Ok(self.reassign_project(old, Some(new), now))
+    }
+
+    /// Deletes the project, its tasks are kept without one. Returns how many tasks it had.
+    pub fn delete_project(&mut self, name: &str, now: OffsetDateTime) -> Result<usize, StoreError> {
+        if !self.container.projects.remove(name) {
+            return Err(StoreError::ProjectNotFound(name.to_string()));
+        }
+        Ok(self.reassign_project(name, None, now))
+    }
+
+    fn reassign_project(&mut self, from: &str, to: Option<&str>, now: OffsetDateTime) -> usize {
+        let mut moved = 0;
+        for task in self.container.tasks.values_mut() {
+            if task.project.as_deref() == Some(from) {
+                task.project = to.map(str::to_string);
+                task.updated = now;
+                moved += 1;
+            }
+        }
+        moved
+    }
+
+    /// Places `id` directly before or after `other` in the manual order. Only the moved task
+    /// gets a new rank unless the gap between its neighbours is too small to split.
+    pub fn move_task(&mut self, id: u64, other: u64, after: bool) -> Result<(), StoreError> {
+        if !self.container.tasks.contains_key(&id) {
+            return Err(StoreError::NotFound(id));
+        }
+
+        let mut order = self.manual_order();
+        order.retain(|i| *i != id);
+        let target = order
+            .iter()
+            .position(|i| *i == other)
+            .ok_or(StoreError::NotFound(other))?;
+        let position = if after { target + 1 } else { target };
+        order.insert(position, id);
+
+        let rank_at = |i: usize| self.container.tasks[&order[i]].rank(order[i]);
+        let next = (position + 1 < order.len()).then(|| rank_at(position + 1));
+        let previous = (position > 0).then(|| rank_at(position - 1));
+        let rank = match (previous, next) {
+            (Some(previous), Some(next)) => (previous + next) / 2.0,
+            (Some(previous), None) => previous + 1.0,
+            (None, Some(next)) => next - 1.0,
+            (None, None) => 0.0,
+        };
+
+        let fits = previous.is_none_or(|p| p < rank) && next.is_none_or(|n| rank < n);
+        if fits {
+            self.get_mut(id)?.rank = Some(rank);
+        } else {
+            // Floats ran out of room between the neighbours, spread everything out again
+            for (i, task_id) in order.iter().enumerate() {
+                self.get_mut(*task_id)?.rank = Some(i as f64 + 1.0);
+            }
+        }
+        Ok(())
+    }
+
+    /// All task IDs in manual order
+    fn manual_order(&self) -> Vec<u64> {
+        let mut order = self
+            .container
+            .tasks
+            .iter()
+            .map(|(id, task)| (task.rank(*id), *id))
+            .collect::<Vec<_>>();
+        order.sort_by(|(a_rank, a_id), (b_rank, b_id)| {
+            a_rank.total_cmp(b_rank).then(a_id.cmp(b_id))
+        });
+        order.into_iter().map(|(_, id)| id).collect()
+    }
+
+    /// Takes a task out as is, e.g. to archive it. Unlike delete, subtasks and dependent tasks
+    /// keep referring to it.
+    pub fn remove(&mut self, id: u64) -> Result<Task, StoreError> {
+        self.container
+            .tasks
+            .remove(&id)
+            .ok_or(StoreError::NotFound(id))
+    }
+
+    /// Puts a task back under the ID it had, replacing any task with that ID
+    pub fn restore(&mut self, id: u64, task: Task) {
+        if let Some(project) = &task.project {
+            self.container.projects.insert(project.clone());
+        }
+        self.container.next_id = self.container.next_id.max(id + 1);
+        self.container.tasks.insert(id, task);
+    }
+
+    /// Deletes a task, refusing while it still has subtasks
+    pub fn delete(&mut self, id: u64) -> Result<Task, StoreError> {
+        let children = self.children(id);
+        if !children.is_empty() {
+            return Err(StoreError::HasSubtasks(id, children));
+        }
+
+        let task = self
+            .container
+            .tasks
+            .remove(&id)
+            .ok_or(StoreError::NotFound(id))?;
+        self.forget_dependency(id);
+        Ok(task)
+    }
+
+    /// Deletes a task together with all of its subtasks, returning every deleted ID
+    pub fn delete_cascade(&mut self, id: u64) -> Result<Vec<u64>, StoreError> {
+        if !self.container.tasks.contains_key(&id) {
+            return Err(StoreError::NotFound(id));
+        }
+
+        let mut deleted = Vec::new();
+        let mut pending = vec![id];
+        while let Some(next) = pending.pop() {
+            pending.extend(self.children(next));
+            self.container.tasks.remove(&next);
+            self.forget_dependency(next);
+            deleted.push(next);
+        }
+        deleted.sort_unstable();
+        Ok(deleted)
+    }
+
+    /// Returns whether the dependency was newly added. Dependencies that would form a cycle
+    /// are refused.
+    pub fn add_dependency(
+        &mut self,
+        id: u64,
+        on: u64,
+        now: OffsetDateTime,
+    ) -> Result<bool, StoreError> {
+        if !self.container.tasks.contains_key(&on) {
+            return Err(StoreError::NotFound(on));
+        }
+        if self.depends_on(on, id) {
+            return Err(StoreError::DependencyCycle(id, on));
+        }
+
+        let task = self.get_mut(id)?;
+        if task.depends_on.contains(&on) {
+            return Ok(false);
+        }
+        task.depends_on.push(on);
+        task.depends_on.sort_unstable();
+        task.updated = now;
+        Ok(true)
+    }
+
+    /// Incomplete tasks that `id` depends on
+    pub fn blockers(&self, id: u64) -> Vec<u64> {
+        let Some(task) = self.container.tasks.get(&id) else {
+            return Vec::new();
+        };
+        task.depends_on
+            .iter()
+            .copied()
+            .filter(|dependency| {
+                self.container
+                    .tasks
+                    .get(dependency)
+                    .is_some_and(|task| task.status != TaskStatus::Complete)
+            })
+            .collect()
+    }
+
+    /// Whether `id` depends on `other`, directly or through other tasks
+    fn depends_on(&self, id: u64, other: u64) -> bool {
+        let mut seen = BTreeSet::new();
+        let mut pending = vec![id];
+        while let Some(next) = pending.pop() {
+            if next == other {
+                return true;
+            }
+            if seen.insert(next)
+                && let Some(task) = self.container.tasks.get(&next)
+            {
+                pending.extend(&task.depends_on);
+            }
+        }
+        false
+    }
+
+    /// Subtasks of `id` at any depth
+    pub fn descendants(&self, id: u64) -> Vec<u64> {
+        let mut descendants = Vec::new();
+        let mut pending = self.children(id);
+        while let Some(next) = pending.pop() {
+            pending.extend(self.children(next));
+            descendants.push(next);
+        }
+        descendants.sort_unstable();
+        descendants
+    }
+
+    /// Direct subtasks of `id`
+    pub fn children(&self, id: u64) -> Vec<u64> {
+        let mut children = self
+            .container
+            .tasks
+            .iter()
+            .filter(|(_, task)| task.parent == Some(id))
+            .map(|(child, _)| *child)
+            .collect::<Vec<_>>();
+        children.sort_unstable();
+        children
+    }
+
+    /// Completing a recurring task creates its next occurrence, whose ID is returned
+    pub fn set_status(
+        &mut self,
+        id: u64,
+        status: TaskStatus,
+        now: OffsetDateTime,
+    ) -> Result<Option<u64>, StoreError> {
+        let task = self.get_mut(id)?;
+        let completed = task.status != TaskStatus::Complete && status == TaskStatus::Complete;
+        // Worked out first so a failure leaves the task as it was
+        let due = match task.repeat {
+            Some(repeat) if completed => Some(
+                repeat
+                    .next(task.due.unwrap_or(now))
+                    .ok_or(StoreError::RepeatOutOfRange(id))?,
+            ),
+            _ => None,
+        };
+        task.status = status;
+        task.updated = now;
+
+        let next = match (task.repeat, due) {
+            (Some(repeat), Some(due)) => {
+                let mut next = Task::new(task.description.clone(), now);
+                next.due = Some(due);
+                next.priority = task.priority;
+                next.tags = task.tags.clone();
+                next.project = task.project.clone();
+                next.parent = task.parent;
+                next.repeat = Some(repeat);
+                next
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(self.insert(next)))
+    }
+
+    pub fn set_repeat(
+        &mut self,
+        id: u64,
+        repeat: Option<Recurrence>,
+        now: OffsetDateTime,
+    ) -> Result<(), StoreError> {
+        let task = self.get_mut(id)?;
+        task.repeat = repeat;
+        task.updated = now;
+        Ok(())
+    }
+
+    /// Sets the task to blocked until every condition holds
+    pub fn block(
+        &mut self,
+        id: u64,
+        conditions: Vec<Condition>,
+        now: OffsetDateTime,
+    ) -> Result<(), StoreError> {
+        let task = self.get_mut(id)?;
+        task.status = TaskStatus::Other(BLOCKED.to_string());
+        task.waiting_on = conditions;
+        task.updated = now;
+        Ok(())
+    }
+
+    /// Moves a task that was waiting on conditions back to Todo
+    pub fn unblock(&mut self, id: u64, now: OffsetDateTime) -> Result<(), StoreError> {
+        let task = self.get_mut(id)?;
+        task.status = TaskStatus::Todo;
+        task.waiting_on.clear();
+        task.updated = now;
+        Ok(())
+    }
+
+    /// Records that the task was looked at in a periodic review, without touching `updated`
+    pub fn mark_reviewed(&mut self, id: u64, now: OffsetDateTime) -> Result<(), StoreError> {
+        self.get_mut(id)?.reviewed = Some(now);
+        Ok(())
+    }
+
+    /// IDs of all other tasks whose description or notes mention `#<id>`
+    pub fn backlinks(&self, id: u64) -> Vec<u64> {
+        let mentions = |text: &str| references::parse_references(text).contains(&id);
+        let mut referrers = self
+            .container
+            .tasks
+            .iter()
+            .filter(|(other, task)| {
+                **other != id
+                    && (mentions(&task.description)
+                        || task.notes.iter().any(|note| mentions(&note.text)))
+            })
+            .map(|(other, _)| *other)
+            .collect::<Vec<_>>();
+        referrers.sort_unstable();
+        referrers
+    }
+
+    /// `#<id>` references in `text` that do not point at an existing task
+    pub fn unknown_references(&self, text: &str) -> Vec<u64> {
+        references::parse_references(text)
+            .into_iter()
+            .filter(|id| !self.container.tasks.contains_key(id))
+            .collect()
+    }
+
+    fn forget_dependency(&mut self, id: u64) {
+        for task in self.container.tasks.values_mut() {
+            task.depends_on.retain(|dependency| *dependency != id);
+        }
+    }
+
+    fn get_mut(&mut self, id: u64) -> Result<&mut Task, StoreError> {
+        self.container
+            .tasks
+            .get_mut(&id)
+            .ok_or(StoreError::NotFound(id))
+    }
+}
+
+/// Checks that raw JSON would load as a store, used when repairing a damaged file
+pub fn validate(root: &serde_json::Value) -> Result<(), serde_json::Error> {
+    TaskContainer::deserialize(root).map(|_| ())
+}
+
+/// Older versions kept the last handed out ID in a separate file next to tasks.json
+fn read_legacy_id(path: &Path) -> Result<u64, StoreError> {
+    match fs::read_to_string(path) {
+        Ok(id) => id.trim().parse::<u64>().map_err(|e| {
+            StoreError::Io(
+                path.to_path_buf(),
+                io::Error::new(io::ErrorKind::InvalidData, e),
+            )
+        }),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(StoreError::Io(path.to_path_buf(), e)),
+    }
+}
+
+/// Optional durations as whole seconds
+mod seconds {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        duration
+            .map(|duration| duration.whole_seconds())
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Ok(Option::<i64>::deserialize(deserializer)?.map(Duration::seconds))
+    }
+}
+
+/// Moves the projects of `container` to their state before `op`, or after it when not `reverse`
+fn replay_projects(container: &mut TaskContainer, op: &Operation, reverse: bool) {
+    let Some(projects) = &op.projects else {
+        return;
+    };
+    let (from, to) = if reverse {
+        (&projects.after, &projects.before)
+    } else {
+        (&projects.before, &projects.after)
+    };
+    container
+        .projects
+        .retain(|p| !from.contains(p) || to.contains(p));
+    container.projects.extend(to.difference(from).cloned());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("taskr-store-{name}-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn save_task(path: &Path, description: &str) {
+        let mut store = TaskStore::open(path).unwrap();
+        store.add(description.to_string(), OffsetDateTime::UNIX_EPOCH);
+        store.save().unwrap();
+    }
+
+    fn descriptions(store: &TaskStore) -> Vec<String> {
+        let mut tasks = store.list(None);
+        tasks.sort_unstable_by_key(|(id, _)| *id);
+        tasks
+            .into_iter()
+            .map(|(_, task)| task.description.clone())
+            .collect()
+    }
+
+    fn corrupt(path: &Path) {
+        let json = fs::read_to_string(path).unwrap();
+        fs::write(path, json.replace("second", "CORRUPTED")).unwrap();
+    }
+
+    #[test]
+    fn mismatch_restores_verified_backup() {
+        let path = temp_dir("restore").join("tasks.json");
+        save_task(&path, "first");
+        save_task(&path, "second");
+        corrupt(&path);
+
+        let (store, kept) = TaskStore::open_verified(&path).unwrap();
+        assert!(!store.checksum_mismatch());
+        assert_eq!(descriptions(&store), ["first"]);
+        let kept = kept.expect("The mismatching file is kept");
+        assert!(fs::read_to_string(kept).unwrap().contains("CORRUPTED"));
+    }
+
+    #[test]
+    fn mismatch_does_not_rotate_backup() {
+        let path = temp_dir("rotate").join("tasks.json");
+        save_task(&path, "first");
+        save_task(&path, "second");
+        corrupt(&path);
+        // Without a checksum for the backup it cannot be trusted to restore from
+        fs::remove_file(persist::backup_path(&integrity::sidecar_path(&path))).unwrap();
+        let backup = fs::read(persist::backup_path(&path)).unwrap();
+
+        let (mut store, kept) = TaskStore::open_verified(&path).unwrap();
+        assert!(kept.is_none());
+        assert!(store.checksum_mismatch());
+        store.add("third".to_string(), OffsetDateTime::UNIX_EPOCH);
+        store.save().unwrap();
+        assert_eq!(fs::read(persist::backup_path(&path)).unwrap(), backup);
+    }
+
+    #[test]
+    fn failed_checksum_write_is_an_error() {
+        let path = temp_dir("sidecar").join("tasks.json");
+        save_task(&path, "first");
+        // A directory in the way of the temporary file makes the atomic write fail
+        let sidecar = integrity::sidecar_path(&path);
+        let mut temp = sidecar.as_os_str().to_owned();
+        temp.push(".tmp");
+        fs::create_dir(PathBuf::from(temp)).unwrap();
+
+        let mut store = TaskStore::open(&path).unwrap();
+        store.add("second".to_string(), OffsetDateTime::UNIX_EPOCH);
+        match store.save() {
+            Err(StoreError::Io(failed, _)) => assert_eq!(failed, sidecar),
+            other => panic!("Expected the checksum write to fail, got {other:?}"),
+        }
+        // No stale checksum is left behind to flag the saved tasks as modified
+        assert!(!TaskStore::open(&path).unwrap().checksum_mismatch());
+    }
+
+    #[test]
+    fn crash_between_tasks_and_checksum_keeps_either_version() {
+        let path = temp_dir("crash").join("tasks.json");
+        save_task(&path, "first");
+
+        // Interrupted before the tasks are replaced
+        let mut store = TaskStore::open(&path).unwrap();
+        store.add("second".to_string(), OffsetDateTime::UNIX_EPOCH);
+        let json = store.to_json();
+        integrity::expect_checksum(&path, json.as_bytes()).unwrap();
+        let (loaded, kept) = TaskStore::open_verified(&path).unwrap();
+        assert!(kept.is_none());
+        assert!(!loaded.checksum_mismatch());
+        assert_eq!(descriptions(&loaded), ["first"]);
+
+        // Interrupted after the tasks are replaced, before the old checksum is dropped
+        persist::write_atomic(&path, json.as_bytes()).unwrap();
+        let (loaded, kept) = TaskStore::open_verified(&path).unwrap();
+        assert!(kept.is_none());
+        assert!(!loaded.checksum_mismatch());
+        assert_eq!(descriptions(&loaded), ["first", "second"]);
+    }
+
+    #[test]
+    fn in_progress_has_its_own_status() {
+        assert_eq!("In-Progress".parse(), Ok(TaskStatus::InProgress));
+        assert_eq!("in progress".parse(), Ok(TaskStatus::InProgress));
+
+        let legacy: TaskStatus = serde_json::from_str(r#"{"Other": "in-progress"}"#).unwrap();
+        assert_eq!(legacy, TaskStatus::InProgress);
+        let custom: TaskStatus = serde_json::from_str(r#"{"Other": "waiting"}"#).unwrap();
+        assert_eq!(custom, TaskStatus::Other("waiting".to_string()));
+        let json = serde_json::to_string(&TaskStatus::InProgress).unwrap();
+        assert_eq!(
+            serde_json::from_str::<TaskStatus>(&json).unwrap(),
+            TaskStatus::InProgress
+        );
+    }
+
+    #[test]
+    fn backlinks_include_notes() {
+        let now = OffsetDateTime::UNIX_EPOCH;
+        let mut store = TaskStore::open(temp_dir("backlinks").join("tasks.json")).unwrap();
+        let target = store.add("release".to_string(), now);
+        let described = store.add("blocks #1".to_string(), now);
+        let noted = store.add("changelog".to_string(), now);
+        store
+            .add_note(noted, "needed before #1 ships".to_string(), now)
+            .unwrap();
+        store.add("unrelated #12".to_string(), now);
+        store
+            .add_note(target, "see #1 itself".to_string(), now)
+            .unwrap();
+        assert_eq!(store.backlinks(target), [described, noted]);
+    }
+
+    #[test]
+    fn repeat_past_the_supported_dates_is_an_error() {
+        let now = OffsetDateTime::UNIX_EPOCH;
+        let mut store = TaskStore::open(temp_dir("repeat").join("tasks.json")).unwrap();
+        let id = store.add("pay".to_string(), now);
+        let due = time::Date::from_calendar_date(9999, time::Month::June, 1).unwrap();
+        store
+            .set_due(id, Some(due.midnight().assume_utc()), now)
+            .unwrap();
+        store
+            .set_repeat(id, Some(Recurrence::Years(1)), now)
+            .unwrap();
+
+        assert!(matches!(
+            store.set_status(id, TaskStatus::Complete, now),
+            Err(StoreError::RepeatOutOfRange(1))
+        ));
+        assert_eq!(store.get(id).unwrap().status, TaskStatus::Todo);
+        assert_eq!(store.list(None).len(), 1);
+    }
+
+    #[test]
+    fn undo_and_redo_report_archive_moves() {
+        let now = OffsetDateTime::UNIX_EPOCH;
+        let path = temp_dir("archive-moves").join("tasks.json");
+        let mut store = TaskStore::open(&path).unwrap();
+        let id = store.add("archived".to_string(), now);
+        store.add("kept".to_string(), now);
+        store.save().unwrap();
+
+        let task = store.remove(id).unwrap();
+        store.mark_archived(id);
+        store.save().unwrap();
+
+        let mut store = TaskStore::open(&path).unwrap();
+        store.undo(1, now).unwrap();
+        let moves = store.take_archive_moves();
+        assert_eq!(moves.unarchive, [id]);
+        assert!(moves.archive.is_empty());
+        store.save().unwrap();
+
+        store.redo(1, now).unwrap();
+        let moves = store.take_archive_moves();
+        assert_eq!(moves.archive, [(id, task)]);
+        assert!(moves.unarchive.is_empty());
+    }
+
+    #[test]
+    fn descendants_reach_every_level() {
+        let now = OffsetDateTime::UNIX_EPOCH;
+        let mut store = TaskStore::open(temp_dir("descendants").join("tasks.json")).unwrap();
+        let parent = store.add("parent".to_string(), now);
+        let mut child = Task::new("child".to_string(), now);
+        child.parent = Some(parent);
+        let child = store.insert(child);
+        let mut grandchild = Task::new("grandchild".to_string(), now);
+        grandchild.parent = Some(child);
+        let grandchild = store.insert(grandchild);
+
+        assert_eq!(store.descendants(parent), [child, grandchild]);
+        assert!(store.descendants(grandchild).is_empty());
+    }
+
+    #[test]
+    fn as_of_reverts_the_journal() {
+        let at = |minutes| OffsetDateTime::UNIX_EPOCH + Duration::minutes(minutes);
+        let path = temp_dir("as-of").join("tasks.json");
+        let mut store = TaskStore::open(&path).unwrap();
+        store.set_operation("add".to_string(), at(1));
+        let id = store.add("task".to_string(), at(1));
+        store.save().unwrap();
+        store.set_operation("note".to_string(), at(2));
+        store.add_note(id, "first".to_string(), at(2)).unwrap();
+        store.save().unwrap();
+        store.set_operation("note".to_string(), at(3));
+        store.add_note(id, "second".to_string(), at(3)).unwrap();
+        store.save().unwrap();
+        store.undo(1, at(4)).unwrap();
+        store.save().unwrap();
+
+        let notes = |time| {
+            let past = store.as_of(time).unwrap();
+            past.get(id).map(|task| task.notes.len())
+        };
+        assert_eq!(notes(at(0)), None);
+        assert_eq!(notes(at(1)), Some(0));
+        assert_eq!(notes(at(2)), Some(1));
+        // Undone at minute 4, so it was there in between
+        assert_eq!(notes(at(3)), Some(2));
+        assert_eq!(notes(at(4)), Some(1));
+    }
+}
@@ -0,0 +1,53 @@
+/* C interface to the taskr task store, implemented in crates/taskr-ffi/src/lib.rs.
+ *
+ * Functions that fail return TASKR_ERROR, 0 or NULL, and taskr_last_error() describes why.
+ * Strings are UTF-8 and NUL terminated. */
+
+#ifndef TASKR_H
+#define TASKR_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define TASKR_OK 0
+#define TASKR_ERROR -1
+
+/* An open tasks.json, locked against the CLI and other handles until it is closed */
+typedef struct TaskrStore TaskrStore;
+
+/* The message of the last error on this thread, or NULL. Valid until the next failing call. */
+const char *taskr_last_error(void);
+
+/* Locks and opens the tasks.json at path, waiting for other processes to release the lock */
+TaskrStore *taskr_open(const char *path);
+
+/* Saves the changes, journaling them under operation so `taskr undo` can revert them */
+int taskr_save(TaskrStore *store, const char *operation);
+
+/* Releases the lock and frees the store without saving. NULL is ignored. */
+void taskr_close(TaskrStore *store);
+
+/* Adds a todo task, returning its ID or 0 on failure */
+uint64_t taskr_add(TaskrStore *store, const char *description);
+
+/* Sets the status, e.g. "todo", "complete" or "someday" */
+int taskr_set_status(TaskrStore *store, uint64_t id, const char *status);
+
+/* Deletes a task without subtasks */
+int taskr_delete(TaskrStore *store, uint64_t id);
+
+/* The tasks as the JSON array `taskr list --output json` prints, a NULL status lists every
+ * task. Free the result with taskr_string_free. */
+char *taskr_list_json(TaskrStore *store, const char *status);
+
+/* Frees a string returned by this library. NULL is ignored. */
+void taskr_string_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,364 @@
+//! A C ABI over taskr-core, so other languages can work on the same tasks.json as the CLI.
+//! `include/taskr.h` declares these functions.
+//!
+//! Functions that fail return `TASKR_ERROR`, 0 or null, and `taskr_last_error` describes why.
+
+use std::{
+    cell::RefCell,
+    ffi::{CStr, CString, c_char, c_int},
+    path::PathBuf,
+    ptr,
+};
+
+use taskr_core::{
+    parse,
+    persist::{self, Lock},
+    store::{TaskStatus, TaskStore},
+};
+use taskr_sync::export;
+
+pub const TASKR_OK: c_int = 0;
+pub const TASKR_ERROR: c_int = -1;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// An open store. It holds the same lock as the CLI until it is closed.
+pub struct TaskrStore {
+    store: TaskStore,
+    _lock: Lock,
+}
+
+fn set_error(message: impl ToString) {
+    // Interior NUL bytes would cut the message short in C, so they are dropped
+    let message = message.to_string().replace('\0', "");
+    let message = CString::new(message).expect("NUL bytes were removed");
+    LAST_ERROR.with(|error| *error.borrow_mut() = Some(message));
+}
+
+/// # Safety
+/// `s` is null or a valid NUL terminated string
+unsafe fn read_str<'a>(s: *const c_char, name: &str) -> Option<&'a str> {
+    if s.is_null() {
+        set_error(format!("{name} is null"));
+        return None;
+    }
+    match unsafe { CStr::from_ptr(s) }.to_str() {
+        Ok(s) => Some(s),
+        Err(_) => {
+            set_error(format!("{name} is not valid UTF-8"));
+            None
+        }
+    }
+}
+
+/// # Safety
+/// `store` is null or a pointer returned by `taskr_open` that has not been closed
+unsafe fn read_store<'a>(store: *mut TaskrStore) -> Option<&'a mut TaskrStore> {
+    let store = unsafe { store.as_mut() };
+    if store.is_none() {
+        set_error("store is null");
+    }
+    store
+}
+
+fn into_c_string(s: String) -> *mut c_char {
+    match CString::new(s) {
+        Ok(s) => s.into_raw(),
+        Err(e) => {
+            set_error(e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// The message of the last error on this thread, or null. It stays valid until the next call
+/// that fails on the same thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn taskr_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| error.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+/// Locks and opens the tasks.json at `path`, waiting for other processes to release the lock.
+/// Returns null on failure.
+///
+/// # Safety
+/// `path` is null or a valid NUL terminated string
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn taskr_open(path: *const c_char) -> *mut TaskrStore {
+    let Some(path) = (unsafe { read_str(path, "path") }) else {
+        return ptr::null_mut();
+    };
+    let path = PathBuf::from(path);
+    let lock = match persist::lock(&path) {
+        Ok(lock) => lock,
+        Err(e) => {
+            set_error(format!(
+                "Failed to lock {:?}: {e}",
+                persist::lock_path(&path)
+            ));
+            return ptr::null_mut();
+        }
+    };
+    match TaskStore::open(path) {
+        Ok(store) => Box::into_raw(Box::new(TaskrStore { store, _lock: lock })),
+        Err(e) => {
+            set_error(e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Saves the changes, journaling them under `operation` so `taskr undo` can revert them
+///
+/// # Safety
+/// `store` comes from `taskr_open`, `operation` is null or a valid NUL terminated string
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn taskr_save(store: *mut TaskrStore, operation: *const c_char) -> c_int {
+    let (Some(store), Some(operation)) = (unsafe { read_store(store) }, unsafe {
+        read_str(operation, "operation")
+    }) else {
+        return TASKR_ERROR;
+    };
+    store
+        .store
+        .set_operation(operation.to_string(), parse::now());
+    match store.store.save() {
+        Ok(()) => TASKR_OK,
+        Err(e) => {
+            set_error(e);
+            TASKR_ERROR
+        }
+    }
+}
+
+/// Releases the lock and frees the store without saving. Null is ignored.
+///
+/// # Safety
+/// `store` is null or comes from `taskr_open` and is not used afterwards
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn taskr_close(store: *mut TaskrStore) {
+    if !store.is_null() {
+        drop(unsafe { Box::from_raw(store) });
+    }
+}
+
+/// Adds a todo task, returning its ID or 0 on failure
+///
+/// # Safety
+/// `store` comes from `taskr_open`, `description` is null or a valid NUL terminated string
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn taskr_add(store: *mut TaskrStore, description: *const c_char) -> u64 {
+    let (Some(store), Some(description)) = (unsafe { read_store(store) }, unsafe {
+        read_str(description, "description")
+    }) else {
+        return 0;
+    };
+    if description.trim().is_empty() {
+        set_error("description is empty");
+        return 0;
+    }
+    store.store.add(description.to_string(), parse::now())
+}
+
+/// Sets the status, e.g. "todo", "complete" or "someday". Completing a recurring task creates
+/// its next occurrence like the CLI does.
+///
+/// # Safety
+/// `store` comes from `taskr_open`, `status` is null or a valid NUL terminated string
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn taskr_set_status(
+    store: *mut TaskrStore,
+    id: u64,
+    status: *const c_char,
+) -> c_int {
+    let (Some(store), Some(status)) = (unsafe { read_store(store) }, unsafe {
+        read_str(status, "status")
+    }) else {
+        return TASKR_ERROR;
+    };
+    let status = status.parse::<TaskStatus>().expect("Any status parses");
+    match store.store.set_status(id, status, parse::now()) {
+        Ok(_) => TASKR_OK,
+        Err(e) => {
+            set_error(e);
+            TASKR_ERROR
+        }
+    }
+}
+
+/// Deletes a task without subtasks
+///
+/// # Safety
+/// `store` comes from `taskr_open`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn taskr_delete(store: *mut TaskrStore, id: u64) -> c_int {
+    let Some(store) = (unsafe { read_store(store) }) else {
+        return TASKR_ERROR;
+    };
+    match store.store.delete(id) {
+        Ok(_) => TASKR_OK,
+        Err(e) => {
+            set_error(e);
+            TASKR_ERROR
+        }
+    }
+}
+
+/// The tasks as the JSON array `taskr list --output json` prints, ordered by ID. A null
+/// `status` lists every task. Free the result with `taskr_string_free`.
+///
+/// # Safety
+/// `store` comes from `taskr_open`, `status` is null or a valid NUL terminated string
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn taskr_list_json(
+    store: *mut TaskrStore,
+    status: *const c_char,
+) -> *mut c_char {
+    let Some(store) = (unsafe { read_store(store) }) else {
+        return ptr::null_mut();
+    };
+    let status = if status.is_null() {
+        None
+    } else {
+        let Some(status) = (unsafe { read_str(status, "status") }) else {
+            return ptr::null_mut();
+        };
+        Some(status.parse::<TaskStatus>().expect("Any status parses"))
+    };
+    let mut tasks = store.store.list(status.as_ref());
+    tasks.sort_unstable_by_key(|(id, _)| *id);
+    into_c_string(export::json(&tasks))
+}
+
+/// Frees a string returned by this library. Null is ignored.
+///
+/// # Safety
+/// `s` is null or was returned by this library and is not used afterwards
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn taskr_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::*;
+
+    fn temp_path(name: &str) -> CString {
+        let dir = env::temp_dir().join(format!("taskr-ffi-{name}-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        CString::new(dir.join("tasks.json").to_str().unwrap()).unwrap()
+    }
+
+    fn last_error() -> String {
+        let error = taskr_last_error();
+        assert!(!error.is_null(), "no error was set");
+        unsafe { CStr::from_ptr(error) }
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    /// Takes ownership of a string returned by the library
+    fn take_string(s: *mut c_char) -> String {
+        assert!(!s.is_null(), "{}", last_error());
+        let string = unsafe { CStr::from_ptr(s) }.to_str().unwrap().to_string();
+        unsafe { taskr_string_free(s) };
+        string
+    }
+
+    #[test]
+    fn add_list_save_and_reopen() {
+        let path = temp_path("roundtrip");
+        unsafe {
+            let store = taskr_open(path.as_ptr());
+            assert!(!store.is_null(), "{}", last_error());
+            let first = taskr_add(store, c"write docs".as_ptr());
+            let second = taskr_add(store, c"fix bug".as_ptr());
+            assert_eq!((first, second), (1, 2));
+            assert_eq!(
+                taskr_set_status(store, first, c"complete".as_ptr()),
+                TASKR_OK
+            );
+
+            let all = take_string(taskr_list_json(store, ptr::null()));
+            let tasks: serde_json::Value = serde_json::from_str(&all).unwrap();
+            assert_eq!(tasks[0]["description"], "write docs");
+            assert_eq!(tasks[1]["id"], 2);
+            let todo = take_string(taskr_list_json(store, c"todo".as_ptr()));
+            assert!(!todo.contains("write docs") && todo.contains("fix bug"));
+
+            assert_eq!(taskr_save(store, c"ffi test".as_ptr()), TASKR_OK);
+            taskr_close(store);
+
+            let store = taskr_open(path.as_ptr());
+            assert!(!store.is_null(), "{}", last_error());
+            assert_eq!(taskr_delete(store, second), TASKR_OK);
+            let all = take_string(taskr_list_json(store, ptr::null()));
+            assert!(all.contains("write docs") && !all.contains("fix bug"));
+            taskr_close(store);
+        }
+    }
+
+    #[test]
+    fn null_pointers_are_errors() {
+        unsafe {
+            assert!(taskr_open(ptr::null()).is_null());
+            assert_eq!(last_error(), "path is null");
+
+            assert_eq!(taskr_add(ptr::null_mut(), c"task".as_ptr()), 0);
+            assert_eq!(last_error(), "store is null");
+            assert_eq!(
+                taskr_set_status(ptr::null_mut(), 1, c"todo".as_ptr()),
+                TASKR_ERROR
+            );
+            assert!(taskr_list_json(ptr::null_mut(), ptr::null()).is_null());
+            assert_eq!(taskr_delete(ptr::null_mut(), 1), TASKR_ERROR);
+
+            let path = temp_path("nulls");
+            let store = taskr_open(path.as_ptr());
+            assert_eq!(taskr_add(store, ptr::null()), 0);
+            assert_eq!(last_error(), "description is null");
+            assert_eq!(taskr_save(store, ptr::null()), TASKR_ERROR);
+            assert_eq!(last_error(), "operation is null");
+            taskr_close(store);
+
+            // Both ignore null
+            taskr_close(ptr::null_mut());
+            taskr_string_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn failures_set_the_last_error() {
+        let path = temp_path("errors");
+        unsafe {
+            let store = taskr_open(path.as_ptr());
+            assert_eq!(taskr_add(store, c"  ".as_ptr()), 0);
+            assert_eq!(last_error(), "description is empty");
+
+            assert_eq!(taskr_set_status(store, 7, c"todo".as_ptr()), TASKR_ERROR);
+            assert!(last_error().contains('7'), "{}", last_error());
+            assert_eq!(taskr_delete(store, 7), TASKR_ERROR);
+
+            let invalid = [0xff_u8, 0];
+            assert_eq!(taskr_add(store, invalid.as_ptr().cast()), 0);
+            assert_eq!(last_error(), "description is not valid UTF-8");
+            taskr_close(store);
+
+            let missing = CString::new("/nonexistent/dir/tasks.json").unwrap();
+            assert!(taskr_open(missing.as_ptr()).is_null());
+            assert!(
+                last_error().starts_with("Failed to lock"),
+                "{}",
+                last_error()
+            );
+        }
+    }
+}
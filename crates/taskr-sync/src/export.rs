@@ -0,0 +1,115 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+use taskr_core::store::{Task, TaskStatus};
+use time::format_description::well_known::Rfc3339;
+
+const CSV_HEADER: &str = "id,status,description,priority,due,tags,project,created,updated";
+
+/// A task as JSON, its ID next to the stored fields
+#[derive(Serialize)]
+pub struct JsonTask<'a> {
+    pub id: u64,
+    #[serde(flatten)]
+    pub task: &'a Task,
+}
+
+/// One row per task with a header, tags separated by spaces and times in RFC 3339
+pub fn csv(tasks: &[(u64, &Task)]) -> String {
+    let mut csv = format!("{CSV_HEADER}\n");
+    for (id, task) in tasks {
+        let fields = [
+            id.to_string(),
+            task.status.to_string(),
+            task.description.clone(),
+            task.priority.map(|p| p.to_string()).unwrap_or_default(),
+            task.due.map(format_time).unwrap_or_default(),
+            task.tags.join(" "),
+            task.project.clone().unwrap_or_default(),
+            format_time(task.created),
+            format_time(task.updated),
+        ];
+        let row = fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// A checklist, completed tasks are checked. Subtasks are indented below their parent when it
+/// is exported too.
+pub fn markdown(tasks: &[(u64, &Task)]) -> String {
+    let mut markdown = String::new();
+    for (id, task, depth) in tree(tasks) {
+        let check = if task.status == TaskStatus::Complete {
+            'x'
+        } else {
+            ' '
+        };
+        let mut line = format!(
+            "{}- [{check}] {}",
+            "  ".repeat(depth),
+            task.description.replace('\n', " ")
+        );
+        if let Some(due) = task.due {
+            let date = due.date();
+            line.push_str(&format!(" (due {date})"));
+        }
+        for tag in &task.tags {
+            line.push_str(&format!(" `+{tag}`"));
+        }
+        line.push_str(&format!(" #{id}"));
+        markdown.push_str(&line);
+        markdown.push('\n');
+    }
+    markdown
+}
+
+pub fn json(tasks: &[(u64, &Task)]) -> String {
+    let tasks = tasks
+        .iter()
+        .map(|(id, task)| JsonTask { id: *id, task })
+        .collect::<Vec<_>>();
+    let mut json = serde_json::to_string_pretty(&tasks).expect("Failed to serialize tasks");
+    json.push('\n');
+    json
+}
+
+/// Tasks in their given order with subtasks moved below their parent and their depth, when
+/// the parent is among the tasks too
+pub fn tree<'a>(tasks: &[(u64, &'a Task)]) -> Vec<(u64, &'a Task, usize)> {
+    let listed = tasks.iter().map(|(id, _)| *id).collect::<HashSet<_>>();
+    let mut children: HashMap<u64, Vec<usize>> = HashMap::new();
+    let mut roots = Vec::new();
+    for (i, (_, task)) in tasks.iter().enumerate() {
+        match task.parent {
+            Some(parent) if listed.contains(&parent) => children.entry(parent).or_default().push(i),
+            _ => roots.push(i),
+        }
+    }
+
+    let mut ordered = Vec::new();
+    let mut pending = roots.into_iter().rev().map(|i| (i, 0)).collect::<Vec<_>>();
+    while let Some((i, depth)) = pending.pop() {
+        let (id, task) = tasks[i];
+        ordered.push((id, task, depth));
+
+        if let Some(subtasks) = children.get(&id) {
+            pending.extend(subtasks.iter().rev().map(|i| (*i, depth + 1)));
+        }
+    }
+    ordered
+}
+
+/// Quotes fields containing separators, quotes or line breaks, doubling inner quotes
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn format_time(time: time::OffsetDateTime) -> String {
+    time.format(&Rfc3339).expect("Failed to format time")
+}
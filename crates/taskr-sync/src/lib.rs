@@ -0,0 +1,5 @@
+//! Conversions between the task store and other tools' formats
+
+pub mod export;
+pub mod taskwarrior;
+pub mod todotxt;
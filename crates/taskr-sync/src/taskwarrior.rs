@@ -0,0 +1,374 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use taskr_core::{
+    generate::Rng,
+    integrity,
+    recurrence::Recurrence,
+    store::{Note, Priority, Task, TaskStatus},
+};
+use time::{OffsetDateTime, PrimitiveDateTime, UtcOffset, format_description};
+
+/// Taskwarrior writes UTC times in the compact ISO 8601 form, e.g. 20250701T120000Z
+const TIME_FORMAT: &str = "[year][month][day]T[hour][minute][second]Z";
+
+/// Tags that stand in for the someday and in-progress statuses, which Taskwarrior does not have
+const SOMEDAY_TAG: &str = "someday";
+const IN_PROGRESS_TAG: &str = "in-progress";
+
+/// A task as `task export` writes it. Fields taskly has no use for, such as urgency, are left
+/// out.
+#[derive(Deserialize, Serialize)]
+struct WarriorTask {
+    uuid: String,
+    description: String,
+    status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    entry: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    modified: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    end: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    due: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    priority: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    depends: Option<Depends>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    recur: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    annotations: Vec<Annotation>,
+}
+
+/// Taskwarrior 2.6 and later write a list, older versions a comma separated string
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum Depends {
+    List(Vec<String>),
+    Joined(String),
+}
+
+#[derive(Deserialize, Serialize)]
+struct Annotation {
+    entry: String,
+    description: String,
+}
+
+/// A task read from a Taskwarrior export along with the UUIDs of the tasks it depends on
+pub struct Imported {
+    pub task: Task,
+    pub depends: Vec<String>,
+}
+
+/// Reads the JSON array `task export` writes. Deleted tasks and the templates of recurring
+/// tasks are skipped, their pending instances come along instead.
+pub fn parse(json: &str, now: OffsetDateTime) -> Result<Vec<Imported>, String> {
+    let tasks: Vec<WarriorTask> =
+        serde_json::from_str(json).map_err(|e| format!("not a Taskwarrior export: {e}"))?;
+
+    let mut imported = Vec::new();
+    for warrior in tasks {
+        let status = match warrior.status.as_str() {
+            "pending" => TaskStatus::Todo,
+            "completed" => TaskStatus::Complete,
+            "waiting" => TaskStatus::Someday,
+            "deleted" | "recurring" => continue,
+            other => TaskStatus::Other(other.to_string()),
+        };
+
+        let mut task = Task::new(warrior.description, now);
+        task.status = status;
+        task.created = parse_time(warrior.entry.as_deref())?.unwrap_or(now);
+        task.updated = parse_time(warrior.end.as_deref().or(warrior.modified.as_deref()))?
+            .unwrap_or(task.created);
+        task.due = parse_time(warrior.due.as_deref())?;
+        task.priority = match warrior.priority.as_deref() {
+            Some("H") => Some(Priority::High),
+            Some("M") => Some(Priority::Medium),
+            Some("L") => Some(Priority::Low),
+            _ => None,
+        };
+        task.project = warrior.project.filter(|project| !project.trim().is_empty());
+        for tag in warrior.tags {
+            // The tags export gives the statuses Taskwarrior lacks, custom ones stay tags
+            match (&task.status, tag.as_str()) {
+                (TaskStatus::Todo, SOMEDAY_TAG) => task.status = TaskStatus::Someday,
+                (TaskStatus::Todo, IN_PROGRESS_TAG) => task.status = TaskStatus::InProgress,
+                _ if !task.has_tag(&tag) => task.tags.push(tag),
+                _ => {}
+            }
+        }
+        task.repeat = warrior
+            .recur
+            .and_then(|recur| recur.parse::<Recurrence>().ok());
+        for annotation in warrior.annotations {
+            task.notes.push(Note {
+                time: parse_time(Some(&annotation.entry))?.unwrap_or(task.created),
+                text: annotation.description,
+            });
+        }
+        task.notes.sort_by_key(|note| note.time);
+        task.uuid = Some(warrior.uuid);
+
+        let depends = match warrior.depends {
+            Some(Depends::List(uuids)) => uuids,
+            Some(Depends::Joined(uuids)) => uuids
+                .split(',')
+                .map(str::trim)
+                .filter(|uuid| !uuid.is_empty())
+                .map(str::to_string)
+                .collect(),
+            None => Vec::new(),
+        };
+        imported.push(Imported { task, depends });
+    }
+    Ok(imported)
+}
+
+/// The tasks as a JSON array `task import` accepts. Tasks that never came from Taskwarrior get a
+/// UUID derived from their ID and creation time, so exporting twice gives the same one. Someday,
+/// in-progress and custom statuses have no Taskwarrior counterpart, those tasks are pending with
+/// the status as a tag. `parse` turns the someday and in-progress tags back into the status, a
+/// custom status stays a tag.
+pub fn format(tasks: &[(u64, &Task)]) -> String {
+    let uuids = tasks
+        .iter()
+        .map(|(id, task)| (*id, uuid(*id, task)))
+        .collect::<HashMap<_, _>>();
+
+    let warriors = tasks
+        .iter()
+        .map(|(id, task)| {
+            let mut tags = task.tags.clone();
+            let status = match &task.status {
+                TaskStatus::Todo => "pending",
+                TaskStatus::Complete => "completed",
+                TaskStatus::Someday => {
+                    tags.push(SOMEDAY_TAG.to_string());
+                    "pending"
+                }
+                TaskStatus::InProgress => {
+                    tags.push(IN_PROGRESS_TAG.to_string());
+                    "pending"
+                }
+                TaskStatus::Other(status) => {
+                    tags.push(status.replace(char::is_whitespace, "-"));
+                    "pending"
+                }
+            };
+            let depends = task
+                .depends_on
+                .iter()
+                .filter_map(|id| uuids.get(id).cloned())
+                .collect::<Vec<_>>();
+
+            WarriorTask {
+                uuid: uuids[id].clone(),
+                description: task.description.clone(),
+                status: status.to_string(),
+                entry: Some(format_time(task.created)),
+                modified: Some(format_time(task.updated)),
+                end: (task.status == TaskStatus::Complete).then(|| format_time(task.updated)),
+                due: task.due.map(format_time),
+                priority: task.priority.map(|priority| {
+                    match priority {
+                        Priority::Critical | Priority::High => "H",
+                        Priority::Medium => "M",
+                        Priority::Low => "L",
+                    }
+                    .to_string()
+                }),
+                project: task.project.clone(),
+                tags,
+                depends: (!depends.is_empty()).then_some(Depends::List(depends)),
+                // Recurring tasks need a template in Taskwarrior, the instance goes as a plain task
+                recur: None,
+                annotations: task
+                    .notes
+                    .iter()
+                    .map(|note| Annotation {
+                        entry: format_time(note.time),
+                        description: note.text.clone(),
+                    })
+                    .collect(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut json = serde_json::to_string_pretty(&warriors).expect("Failed to serialize tasks");
+    json.push('\n');
+    json
+}
+
+/// The stored UUID, or a random looking version 4 UUID seeded by the task. The seed is hashed
+/// with the checksum's FNV-1a, so exports give a task the same UUID with every build.
+fn uuid(id: u64, task: &Task) -> String {
+    if let Some(uuid) = &task.uuid {
+        return uuid.clone();
+    }
+
+    let mut seed = id.to_le_bytes().to_vec();
+    seed.extend(task.created.unix_timestamp_nanos().to_le_bytes());
+    let mut rng = Rng::new(integrity::checksum(&seed));
+    let (high, low) = (rng.next_u64(), rng.next_u64());
+    let high = (high & !0xf000) | 0x4000;
+    let low = (low & !(0b11 << 62)) | (0b10 << 62);
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        high >> 32,
+        (high >> 16) & 0xffff,
+        high & 0xffff,
+        low >> 48,
+        low & 0xffff_ffff_ffff
+    )
+}
+
+fn parse_time(time: Option<&str>) -> Result<Option<OffsetDateTime>, String> {
+    let Some(time) = time else {
+        return Ok(None);
+    };
+    let format = format_description::parse(TIME_FORMAT).expect("Valid format");
+    PrimitiveDateTime::parse(time, &format)
+        .map(|time| Some(time.assume_utc()))
+        .map_err(|_| format!("invalid Taskwarrior time '{time}'"))
+}
+
+fn format_time(time: OffsetDateTime) -> String {
+    let format = format_description::parse(TIME_FORMAT).expect("Valid format");
+    time.to_offset(UtcOffset::UTC)
+        .format(&format)
+        .expect("Failed to format time")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_uuids_are_stable() {
+        let task = Task::new("export me".to_string(), OffsetDateTime::UNIX_EPOCH);
+        let first = uuid(7, &task);
+        assert_eq!(first, "8801b53a-7962-4b01-997e-6990c36512ee");
+        assert_eq!(first, uuid(7, &task));
+        assert_ne!(first, uuid(8, &task));
+        assert_eq!(&first[14..15], "4");
+        assert!(matches!(&first[19..20], "8" | "9" | "a" | "b"));
+    }
+
+    fn parse_one(warrior: &str) -> Result<Imported, String> {
+        let mut imported = parse(&format!("[{warrior}]"), OffsetDateTime::UNIX_EPOCH)?;
+        assert_eq!(imported.len(), 1);
+        Ok(imported.remove(0))
+    }
+
+    #[test]
+    fn depends_in_either_form() {
+        let list =
+            parse_one(r#"{"uuid":"a","description":"d","status":"pending","depends":["b","c"]}"#);
+        assert_eq!(list.unwrap().depends, ["b", "c"]);
+
+        let joined =
+            parse_one(r#"{"uuid":"a","description":"d","status":"pending","depends":"b, c,"}"#);
+        assert_eq!(joined.unwrap().depends, ["b", "c"]);
+    }
+
+    #[test]
+    fn annotations_become_notes_in_order() {
+        let imported = parse_one(
+            r#"{"uuid":"a","description":"d","status":"pending","entry":"20250101T000000Z",
+                "annotations":[
+                    {"entry":"20250103T120000Z","description":"later"},
+                    {"entry":"20250102T080000Z","description":"earlier"}
+                ]}"#,
+        )
+        .unwrap();
+        let notes = imported
+            .task
+            .notes
+            .iter()
+            .map(|note| (note.time.day(), note.text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(notes, [(2, "earlier"), (3, "later")]);
+    }
+
+    #[test]
+    fn statuses_and_skipped_tasks() {
+        let json = r#"[
+            {"uuid":"1","description":"pending","status":"pending"},
+            {"uuid":"2","description":"completed","status":"completed","end":"20250102T000000Z"},
+            {"uuid":"3","description":"waiting","status":"waiting"},
+            {"uuid":"4","description":"template","status":"recurring","recur":"weekly"},
+            {"uuid":"5","description":"deleted","status":"deleted"},
+            {"uuid":"6","description":"custom","status":"delegated"}
+        ]"#;
+        let imported = parse(json, OffsetDateTime::UNIX_EPOCH).unwrap();
+        let statuses = imported
+            .iter()
+            .map(|imported| (imported.task.description.as_str(), &imported.task.status))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            [
+                ("pending", &TaskStatus::Todo),
+                ("completed", &TaskStatus::Complete),
+                ("waiting", &TaskStatus::Someday),
+                ("custom", &TaskStatus::Other("delegated".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_times_are_errors() {
+        for field in ["entry", "modified", "end", "due"] {
+            let warrior = format!(
+                r#"{{"uuid":"a","description":"d","status":"pending","{field}":"2025-01-01"}}"#
+            );
+            let error = parse_one(&warrior).err().expect("The time does not parse");
+            assert!(error.contains("'2025-01-01'"), "{field}: {error}");
+        }
+        let annotated = r#"{"uuid":"a","description":"d","status":"pending",
+            "annotations":[{"entry":"yesterday","description":"n"}]}"#;
+        assert!(parse_one(annotated).is_err());
+    }
+
+    #[test]
+    fn statuses_survive_a_round_trip_except_custom_ones() {
+        let now = OffsetDateTime::UNIX_EPOCH;
+        let mut tasks = Vec::new();
+        for status in [
+            TaskStatus::Todo,
+            TaskStatus::Someday,
+            TaskStatus::InProgress,
+            TaskStatus::Other("on hold".to_string()),
+        ] {
+            let mut task = Task::new(status.to_string(), now);
+            task.status = status;
+            tasks.push(task);
+        }
+        let listed = tasks
+            .iter()
+            .enumerate()
+            .map(|(id, task)| (id as u64 + 1, task))
+            .collect::<Vec<_>>();
+
+        let imported = parse(&format(&listed), now).unwrap();
+        let statuses = imported
+            .iter()
+            .map(|imported| (&imported.task.status, imported.task.tags.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            [
+                (&TaskStatus::Todo, vec![]),
+                (&TaskStatus::Someday, vec![]),
+                (&TaskStatus::InProgress, vec![]),
+                (&TaskStatus::Todo, vec!["on-hold".to_string()]),
+            ]
+        );
+    }
+}
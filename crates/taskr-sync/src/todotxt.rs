@@ -0,0 +1,227 @@
+use taskr_core::store::{Priority, Task, TaskStatus};
+use time::{Date, OffsetDateTime, PrimitiveDateTime, Time, format_description};
+
+/// A task read from a todo.txt line
+pub struct Imported {
+    pub task: Task,
+    /// The creation date the line gave, without one the task is created `now`
+    pub created: Option<Date>,
+}
+
+/// Reads the tasks of a todo.txt file, one per non-empty line. The first `+project` becomes the
+/// project, `@contexts` and further projects become tags and `due:` sets the due date. These
+/// are taken out of the description, other `key:value` pairs stay in it.
+pub fn parse(text: &str, now: OffsetDateTime) -> Vec<Imported> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| parse_line(line, now))
+        .collect()
+}
+
+/// The tasks in todo.txt format. Tags are written as `@contexts`, and completed tasks keep
+/// their priority as `pri:` since todo.txt drops the leading one on completion.
+pub fn format(tasks: &[(u64, &Task)]) -> String {
+    let mut text = String::new();
+    for (_, task) in tasks {
+        text.push_str(&format_line(task));
+        text.push('\n');
+    }
+    text
+}
+
+fn parse_line(line: &str, now: OffsetDateTime) -> Imported {
+    let mut words = line.split_whitespace().peekable();
+    let completed = words.next_if_eq(&"x").is_some();
+    let mut completion = None;
+    let mut priority = None;
+    if completed {
+        completion = words.next_if(|word| parse_date(word).is_some());
+    } else {
+        priority = words.next_if(|word| parse_priority(word).is_some());
+    }
+    let created = words
+        .next_if(|word| parse_date(word).is_some())
+        .and_then(parse_date);
+
+    let mut task = Task::new(String::new(), now);
+    let mut description = Vec::new();
+    for word in words {
+        if let Some(project) = word.strip_prefix('+').filter(|p| !p.is_empty()) {
+            if task.project.is_none() {
+                task.project = Some(project.to_string());
+            } else if !task.has_tag(project) {
+                task.tags.push(project.to_string());
+            }
+        } else if let Some(context) = word.strip_prefix('@').filter(|c| !c.is_empty()) {
+            if !task.has_tag(context) {
+                task.tags.push(context.to_string());
+            }
+        } else if let Some(due) = word.strip_prefix("due:").and_then(parse_date) {
+            let last_second = Time::from_hms(23, 59, 59).expect("Valid time");
+            task.due = Some(at(due, last_second, now));
+        } else if let Some(letter) = word.strip_prefix("pri:")
+            && let Some(pri) = parse_priority(&format!("({letter})"))
+        {
+            task.priority = Some(pri);
+        } else {
+            description.push(word);
+        }
+    }
+
+    task.description = if description.is_empty() {
+        line.trim().to_string()
+    } else {
+        description.join(" ")
+    };
+    task.priority = priority.and_then(parse_priority).or(task.priority);
+    if let Some(created) = created {
+        task.created = at(created, Time::MIDNIGHT, now);
+        task.updated = task.created;
+    }
+    if completed {
+        task.status = TaskStatus::Complete;
+        task.updated = completion
+            .and_then(parse_date)
+            .map_or(now, |date| at(date, Time::MIDNIGHT, now));
+    }
+    Imported { task, created }
+}
+
+fn format_line(task: &Task) -> String {
+    let mut words = Vec::new();
+    if task.status == TaskStatus::Complete {
+        words.push("x".to_string());
+        words.push(task.updated.date().to_string());
+    } else if let Some(priority) = task.priority {
+        words.push(format!("({})", priority_letter(priority)));
+    }
+    words.push(task.created.date().to_string());
+    words.push(
+        task.description
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" "),
+    );
+
+    if let Some(project) = &task.project {
+        words.push(format!("+{}", project.replace(char::is_whitespace, "_")));
+    }
+    words.extend(task.tags.iter().map(|tag| format!("@{tag}")));
+    if let Some(due) = task.due {
+        words.push(format!("due:{}", due.date()));
+    }
+    if task.status == TaskStatus::Complete
+        && let Some(priority) = task.priority
+    {
+        words.push(format!("pri:{}", priority_letter(priority)));
+    }
+    words.join(" ")
+}
+
+/// `(A)` is the most important, taskly has four levels so D and below are all low
+fn parse_priority(word: &str) -> Option<Priority> {
+    let letter = word.strip_prefix('(')?.strip_suffix(')')?;
+    match letter {
+        "A" => Some(Priority::Critical),
+        "B" => Some(Priority::High),
+        "C" => Some(Priority::Medium),
+        _ if letter.len() == 1 && letter.chars().all(|c| c.is_ascii_uppercase()) => {
+            Some(Priority::Low)
+        }
+        _ => None,
+    }
+}
+
+fn priority_letter(priority: Priority) -> char {
+    match priority {
+        Priority::Critical => 'A',
+        Priority::High => 'B',
+        Priority::Medium => 'C',
+        Priority::Low => 'D',
+    }
+}
+
+fn parse_date(word: &str) -> Option<Date> {
+    let format = format_description::parse("[year]-[month]-[day]").expect("Valid format");
+    Date::parse(word, &format).ok()
+}
+
+/// The date at `time` in the local offset of `now`
+fn at(date: Date, time: Time, now: OffsetDateTime) -> OffsetDateTime {
+    PrimitiveDateTime::new(date, time).assume_offset(now.offset())
+}
+
+#[cfg(test)]
+mod tests {
+    use time::Month;
+
+    use super::*;
+
+    fn now() -> OffsetDateTime {
+        date(2026, 3, 1)
+            .with_time(Time::from_hms(12, 0, 0).unwrap())
+            .assume_utc()
+    }
+
+    fn date(year: i32, month: u8, day: u8) -> Date {
+        Date::from_calendar_date(year, Month::try_from(month).unwrap(), day).unwrap()
+    }
+
+    fn parse_one(line: &str) -> Imported {
+        let mut imported = parse(line, now());
+        assert_eq!(imported.len(), 1);
+        imported.remove(0)
+    }
+
+    #[test]
+    fn priorities_and_dates() {
+        let imported = parse_one("(A) 2026-01-05 call mom");
+        assert_eq!(imported.task.priority, Some(Priority::Critical));
+        assert_eq!(imported.created, Some(date(2026, 1, 5)));
+        assert_eq!(imported.task.created.date(), date(2026, 1, 5));
+        assert_eq!(imported.task.description, "call mom");
+        assert_eq!(parse_one("(F) x").task.priority, Some(Priority::Low));
+
+        let undated = parse_one("call mom");
+        assert_eq!(undated.created, None);
+        assert_eq!(undated.task.created, now());
+    }
+
+    #[test]
+    fn completion_and_creation_dates() {
+        let task = parse_one("x 2026-02-02 2026-01-05 file taxes pri:B").task;
+        assert_eq!(task.status, TaskStatus::Complete);
+        assert_eq!(task.updated.date(), date(2026, 2, 2));
+        assert_eq!(task.created.date(), date(2026, 1, 5));
+        assert_eq!(task.priority, Some(Priority::High));
+        assert_eq!(task.description, "file taxes");
+
+        // Without a completion date the task was completed at the import
+        let task = parse_one("x file taxes").task;
+        assert_eq!((task.status, task.updated), (TaskStatus::Complete, now()));
+    }
+
+    #[test]
+    fn projects_contexts_and_due() {
+        let task = parse_one("plan trip +travel +family @phone @phone due:2026-04-01 key:v").task;
+        assert_eq!(task.project.as_deref(), Some("travel"));
+        assert_eq!(task.tags, ["family", "phone"]);
+        assert_eq!(task.due.unwrap().date(), date(2026, 4, 1));
+        assert_eq!(task.description, "plan trip key:v");
+        // A lone + or @ is part of the text
+        assert_eq!(parse_one("a + b @ c").task.description, "a + b @ c");
+        assert_eq!(parse("\n  \n", now()).len(), 0);
+    }
+
+    #[test]
+    fn round_trips() {
+        for line in [
+            "(B) 2026-01-05 call mom +family @phone due:2026-04-01",
+            "2026-01-05 read",
+            "x 2026-02-02 2026-01-05 file taxes @home pri:A",
+        ] {
+            let task = parse_one(line).task;
+            assert_eq!(format(&[(1, &task)]), format!("{line}\n"));
+        }
+    }
+}
@@ -1,2 +0,0 @@
-pub mod cli;
-pub mod utils;